@@ -9,9 +9,14 @@ mod benchmarks {
     const MEMORY_SIZE: usize = 100_000;
     const NUM_BUCKETS: usize = 5;
     const FINGERPRINT_SIZE: usize = 3;
+    const NUM_HASHES: usize = 3;
 
     use criterion::Criterion;
-    use qht::{QQuotientHashTableD, QQuotientHashTable, Element, Filter, QuotientHashTable};
+    use qht::streams::Uniform;
+    use qht::{
+        BloomFilter, CuckooFilter, Element, Filter, QQuotientHashTable, QQuotientHashTableD,
+        QuotientHashTable, StableBloomFilter,
+    };
 
     use rand::{rngs::StdRng, FromEntropy, RngCore};
 
@@ -19,11 +24,8 @@ mod benchmarks {
         let mut rng = StdRng::from_entropy();
 
         c.bench_function("RNG", move |b| {
-            b.iter(|| {
-                let e = Element {
-                    value: rng.next_u64() % MAX_ELEMENT_VALUE,
-                };
-                e
+            b.iter(|| Element {
+                value: rng.next_u64() % MAX_ELEMENT_VALUE,
             })
         });
     }
@@ -52,6 +54,24 @@ mod benchmarks {
         c.bench_function("QuotientHashTable::insert", move |b| b.iter(|| f.insert(e)));
     }
 
+    pub fn bench_insert_qht_pow2(c: &mut Criterion) {
+        let mut f = QuotientHashTable::new(MEMORY_SIZE, NUM_BUCKETS, FINGERPRINT_SIZE)
+            .with_power_of_two_cells();
+        let e = Element { value: 1234 };
+        c.bench_function("QuotientHashTable::insert (power of two cells)", move |b| {
+            b.iter(|| f.insert(e))
+        });
+    }
+
+    pub fn bench_insert_qht_stream(c: &mut Criterion) {
+        let mut f = QuotientHashTable::new(MEMORY_SIZE, NUM_BUCKETS, FINGERPRINT_SIZE);
+        let stream: Vec<u64> = Uniform::new(MAX_ELEMENT_VALUE, 0).take(10_000).collect();
+        let mut elements = stream.into_iter().cycle();
+        c.bench_function("QuotientHashTable::insert (uniform stream)", move |b| {
+            b.iter(|| f.insert(elements.next().unwrap()))
+        });
+    }
+
     pub fn bench_insert_dqht(c: &mut Criterion) {
         let mut f = QQuotientHashTable::new(MEMORY_SIZE, NUM_BUCKETS, FINGERPRINT_SIZE);
         let e = Element { value: 1234 };
@@ -74,6 +94,15 @@ mod benchmarks {
         c.bench_function("QuotientHashTable::lookup", move |b| b.iter(|| f.lookup(e)));
     }
 
+    pub fn bench_lookup_qht_pow2(c: &mut Criterion) {
+        let f = QuotientHashTable::new(MEMORY_SIZE, NUM_BUCKETS, FINGERPRINT_SIZE)
+            .with_power_of_two_cells();
+        let e = Element { value: 1234 };
+        c.bench_function("QuotientHashTable::lookup (power of two cells)", move |b| {
+            b.iter(|| f.lookup(e))
+        });
+    }
+
     pub fn bench_lookup_dqht(c: &mut Criterion) {
         let f = QQuotientHashTable::new(MEMORY_SIZE, NUM_BUCKETS, FINGERPRINT_SIZE);
         let e = Element { value: 1234 };
@@ -90,20 +119,46 @@ mod benchmarks {
         });
     }
 
+    pub fn bench_insert_bloom(c: &mut Criterion) {
+        let mut f = BloomFilter::new(MEMORY_SIZE, NUM_HASHES);
+        let mut elements = Uniform::new(MAX_ELEMENT_VALUE, 0);
+        c.bench_function("BloomFilter::insert (uniform stream)", move |b| {
+            b.iter(|| f.insert(elements.next().unwrap()))
+        });
+    }
+
+    pub fn bench_insert_stable_bloom(c: &mut Criterion) {
+        let mut f = StableBloomFilter::new(MEMORY_SIZE, 2, NUM_HASHES, 10);
+        let mut elements = Uniform::new(MAX_ELEMENT_VALUE, 0);
+        c.bench_function("StableBloomFilter::insert (uniform stream)", move |b| {
+            b.iter(|| f.insert(elements.next().unwrap()))
+        });
+    }
+
+    pub fn bench_insert_cuckoo(c: &mut Criterion) {
+        let mut f = CuckooFilter::new(MEMORY_SIZE, 4, 8).evict_on_failure();
+        let mut elements = Uniform::new(MAX_ELEMENT_VALUE, 0);
+        c.bench_function("CuckooFilter::insert (uniform stream)", move |b| {
+            b.iter(|| f.insert(elements.next().unwrap()))
+        });
+    }
 }
 
-/// General tests
+// General tests
 criterion_group!(general, benchmarks::bench_rng,);
 
-/// Tests for QHT
+// Tests for QHT
 criterion_group!(
     bench_qht,
     benchmarks::bench_new_qht,
     benchmarks::bench_insert_qht,
+    benchmarks::bench_insert_qht_pow2,
+    benchmarks::bench_insert_qht_stream,
     benchmarks::bench_lookup_qht,
+    benchmarks::bench_lookup_qht_pow2,
 );
 
-/// Tests for DQHT
+// Tests for DQHT
 criterion_group!(
     bench_dqht,
     benchmarks::bench_new_dqht,
@@ -111,7 +166,7 @@ criterion_group!(
     benchmarks::bench_lookup_dqht,
 );
 
-/// Tests for DQQHT
+// Tests for DQQHT
 criterion_group!(
     bench_dqqht,
     benchmarks::bench_new_dqqht,
@@ -119,5 +174,13 @@ criterion_group!(
     benchmarks::bench_lookup_dqqht,
 );
 
-/// Run tests
-criterion_main!(general, bench_qht, bench_dqht, bench_dqqht);
+// Baselines
+criterion_group!(
+    bench_baselines,
+    benchmarks::bench_insert_bloom,
+    benchmarks::bench_insert_stable_bloom,
+    benchmarks::bench_insert_cuckoo,
+);
+
+// Run tests
+criterion_main!(general, bench_qht, bench_dqht, bench_dqqht, bench_baselines);
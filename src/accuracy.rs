@@ -0,0 +1,394 @@
+use crate::element::Record;
+use crate::filter::Filter;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::mem;
+use std::ops::AddAssign;
+
+// --------------------------------------------------------------------------------
+// Confusion matrix
+
+/// Counts of the answers of a filter against the ground truth
+///
+/// Positives are elements detected as duplicates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConfusionMatrix {
+    /// Duplicates detected as such
+    pub true_positives: u64,
+
+    /// Unique elements detected as duplicates
+    pub false_positives: u64,
+
+    /// Unique elements detected as such
+    pub true_negatives: u64,
+
+    /// Duplicates not detected
+    pub false_negatives: u64,
+}
+
+impl ConfusionMatrix {
+    /// Records an answer of the filter (`detected`) against the ground truth (`duplicate`)
+    pub fn record(&mut self, detected: bool, duplicate: bool) {
+        match (detected, duplicate) {
+            (true, true) => self.true_positives += 1,
+            (true, false) => self.false_positives += 1,
+            (false, false) => self.true_negatives += 1,
+            (false, true) => self.false_negatives += 1,
+        }
+    }
+
+    /// Returns the number of recorded answers
+    pub fn total(&self) -> u64 {
+        self.true_positives + self.false_positives + self.true_negatives + self.false_negatives
+    }
+
+    /// Returns the fraction of unique elements detected as duplicates, or 0 if there are none
+    pub fn false_positive_rate(&self) -> f64 {
+        ratio(
+            self.false_positives,
+            self.false_positives + self.true_negatives,
+        )
+    }
+
+    /// Returns the fraction of duplicates not detected, or 0 if there are none
+    pub fn false_negative_rate(&self) -> f64 {
+        ratio(
+            self.false_negatives,
+            self.false_negatives + self.true_positives,
+        )
+    }
+
+    /// Returns the fraction of correct answers, or 1 if there are none
+    pub fn accuracy(&self) -> f64 {
+        1.0 - ratio(self.false_positives + self.false_negatives, self.total())
+    }
+}
+
+impl AddAssign for ConfusionMatrix {
+    fn add_assign(&mut self, other: Self) {
+        self.true_positives += other.true_positives;
+        self.false_positives += other.false_positives;
+        self.true_negatives += other.true_negatives;
+        self.false_negatives += other.false_negatives;
+    }
+}
+
+fn ratio(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Ground truth
+
+/// Exact duplicate detection
+enum Oracle<K> {
+    /// Every element seen so far
+    All(HashSet<K>),
+
+    /// The last `size` elements, with their number of occurrences among them
+    Window {
+        size: usize,
+        recent: VecDeque<K>,
+        counts: HashMap<K, usize>,
+    },
+}
+
+impl<K: Clone + Eq + Hash> Oracle<K> {
+    /// Returns whether `e` is a duplicate, and records it
+    fn insert(&mut self, e: K) -> bool {
+        match self {
+            Oracle::All(seen) => !seen.insert(e),
+            Oracle::Window {
+                size,
+                recent,
+                counts,
+            } => {
+                let count = counts.entry(e.clone()).or_insert(0);
+                let duplicate = *count > 0;
+                *count += 1;
+                recent.push_back(e);
+
+                if recent.len() > *size {
+                    let oldest = recent.pop_front().unwrap();
+                    let count = counts.get_mut(&oldest).unwrap();
+                    *count -= 1;
+                    if *count == 0 {
+                        counts.remove(&oldest);
+                    }
+                }
+                duplicate
+            }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Tracker
+
+/// How answers are split into periods
+enum Period {
+    /// Answers are not split
+    Unsplit,
+
+    /// Periods of a number of insertions
+    Insertions(u64),
+
+    /// Periods of `duration` time units, aligned on multiples of `duration`, with the index of
+    /// the current one once a timestamp was seen
+    Time { duration: u64, current: Option<u64> },
+}
+
+/// Wraps a filter, checking each of its answers against an exact oracle
+///
+/// The oracle is either an exact set of all the elements seen (`new`), or of the last ones
+/// (`with_window`), for filters meant to forget old elements. Answers are accumulated in
+/// confusion matrices, in total and per period of insertions or of time (see `with_period` and
+/// `with_time_period`), so that a configuration can be validated on real traffic before running
+/// the filter alone (`into_inner`).
+///
+/// The oracle takes memory proportional to the number of (recent) distinct elements.
+///
+/// # Example
+/// ```rust
+/// use qht::{AccuracyTracker, QuotientHashTable};
+/// let mut tracker = AccuracyTracker::new(QuotientHashTable::new(1024, 4, 8)).with_period(2);
+/// for &e in &[1u64, 2, 1, 3, 1] {
+///     tracker.insert(e);
+/// }
+/// let total = tracker.total();
+/// assert_eq!( total.total(), 5 );
+/// assert_eq!( total.true_positives + total.false_negatives, 2 );
+/// assert_eq!( tracker.windows().len(), 2 );
+/// assert_eq!( tracker.current().total(), 1 );
+///
+/// let filter = tracker.into_inner();
+/// ```
+pub struct AccuracyTracker<F: Filter, K = u64> {
+    /// Tracked filter
+    filter: F,
+
+    /// Ground truth
+    oracle: Oracle<K>,
+
+    /// Answers since the creation of the tracker
+    total: ConfusionMatrix,
+
+    /// Answers in the current period
+    current: ConfusionMatrix,
+
+    /// Answers in the past periods, oldest first
+    windows: Vec<ConfusionMatrix>,
+
+    /// Number of past periods without insertions, which are not recorded in `windows`
+    skipped_periods: u64,
+
+    /// How answers are split into periods
+    period: Period,
+}
+
+impl<F: Filter, K: Clone + Eq + Hash> AccuracyTracker<F, K> {
+    /// Tracks `filter` against an exact set of all the elements seen
+    pub fn new(filter: F) -> Self {
+        Self::with_oracle(filter, Oracle::All(HashSet::new()))
+    }
+
+    /// Tracks `filter` against an exact set of the last `size` elements, or panics if `size` is zero
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::{AccuracyTracker, QuotientHashTable};
+    /// let mut tracker = AccuracyTracker::with_window(QuotientHashTable::new(1024, 4, 8), 2);
+    /// for &e in &[1u64, 2, 3, 1] {
+    ///     tracker.insert(e);
+    /// }
+    /// // 1 is out of the window when repeated, hence a false positive for the filter
+    /// assert_eq!( tracker.total().false_positives, 1 );
+    /// ```
+    pub fn with_window(filter: F, size: usize) -> Self {
+        if size == 0 {
+            panic!("[AccuracyTracker] Incorrect parameters, the window cannot be empty.");
+        }
+        Self::with_oracle(
+            filter,
+            Oracle::Window {
+                size,
+                recent: VecDeque::with_capacity(size + 1),
+                counts: HashMap::new(),
+            },
+        )
+    }
+
+    fn with_oracle(filter: F, oracle: Oracle<K>) -> Self {
+        Self {
+            filter,
+            oracle,
+            total: ConfusionMatrix::default(),
+            current: ConfusionMatrix::default(),
+            windows: Vec::new(),
+            skipped_periods: 0,
+            period: Period::Unsplit,
+        }
+    }
+
+    /// Splits answers into periods of `period` insertions, or panics if `period` is zero
+    pub fn with_period(mut self, period: usize) -> Self {
+        if period == 0 {
+            panic!("[AccuracyTracker] Incorrect parameters, period cannot be zero.");
+        }
+        self.period = Period::Insertions(period as u64);
+        self
+    }
+
+    /// Splits answers into periods of `duration` time units, or panics if `duration` is zero
+    ///
+    /// Periods are aligned on multiples of `duration`, and follow the timestamps given to
+    /// `insert_at` or `insert_record`. Periods without insertions are only counted (see
+    /// `skipped_periods`), so that a jump in time takes no memory. Insertions without a
+    /// timestamp, or with an earlier one than the current period, are recorded in the current
+    /// period.
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::{AccuracyTracker, QuotientHashTable, Record};
+    /// let mut tracker = AccuracyTracker::new(QuotientHashTable::new(1024, 4, 8)).with_time_period(60);
+    /// tracker.insert_at(1, 0);
+    /// tracker.insert_at(1, 59);
+    /// tracker.insert_record(Record::new(2).with_timestamp(130));
+    /// let windows = tracker.windows();
+    /// assert_eq!( windows.len(), 1 );
+    /// assert_eq!( windows[0].total(), 2 ); // From 0 to 59
+    /// assert_eq!( tracker.skipped_periods(), 1 ); // From 60 to 119
+    /// assert_eq!( tracker.current().total(), 1 );
+    /// ```
+    pub fn with_time_period(mut self, duration: u64) -> Self {
+        if duration == 0 {
+            panic!("[AccuracyTracker] Incorrect parameters, duration cannot be zero.");
+        }
+        self.period = Period::Time {
+            duration,
+            current: None,
+        };
+        self
+    }
+
+    /// Inserts `e` in the filter and the oracle, records whether the filter was right, and
+    /// returns the answer of the filter
+    pub fn insert(&mut self, e: K) -> bool {
+        let detected = self.filter.insert(&e);
+        let duplicate = self.oracle.insert(e);
+        self.total.record(detected, duplicate);
+        self.current.record(detected, duplicate);
+
+        if let Period::Insertions(period) = self.period {
+            if self.current.total() == period {
+                let current = mem::replace(&mut self.current, ConfusionMatrix::default());
+                self.windows.push(current);
+            }
+        }
+        detected
+    }
+
+    /// Inserts `e`, which occurred at `timestamp`, as `insert` does
+    ///
+    /// With `with_time_period`, the periods ended by `timestamp` are closed first.
+    pub fn insert_at(&mut self, e: K, timestamp: u64) -> bool {
+        if let Period::Time {
+            duration,
+            ref mut current,
+        } = self.period
+        {
+            let index = timestamp / duration;
+            match *current {
+                Some(previous) if previous < index => {
+                    let window = mem::replace(&mut self.current, ConfusionMatrix::default());
+                    self.windows.push(window);
+                    self.skipped_periods += index - previous - 1;
+                    *current = Some(index);
+                }
+                Some(_) => (),
+                None => *current = Some(index),
+            }
+        }
+        self.insert(e)
+    }
+
+    /// Inserts the key of `record`, at its timestamp if it has one (see `insert_at`)
+    pub fn insert_record(&mut self, record: Record<K>) -> bool {
+        match record.timestamp {
+            Some(timestamp) => self.insert_at(record.key, timestamp),
+            None => self.insert(record.key),
+        }
+    }
+
+    /// Performs a lookup in the filter, which is not recorded
+    pub fn lookup(&self, e: &K) -> bool {
+        self.filter.lookup(e)
+    }
+
+    /// Returns the answers since the creation of the tracker
+    pub fn total(&self) -> ConfusionMatrix {
+        self.total
+    }
+
+    /// Returns the answers of the current period, or since the creation of the tracker if
+    /// answers are not split into periods
+    pub fn current(&self) -> ConfusionMatrix {
+        self.current
+    }
+
+    /// Returns the answers of the past periods, oldest first
+    pub fn windows(&self) -> &[ConfusionMatrix] {
+        &self.windows
+    }
+
+    /// Returns the number of past periods without insertions, which `windows` omits
+    pub fn skipped_periods(&self) -> u64 {
+        self.skipped_periods
+    }
+
+    /// Removes and returns the answers of the past periods, oldest first
+    pub fn drain_windows(&mut self) -> Vec<ConfusionMatrix> {
+        mem::replace(&mut self.windows, Vec::new())
+    }
+
+    /// Returns the tracked filter
+    pub fn filter(&self) -> &F {
+        &self.filter
+    }
+
+    /// Drops the oracle, returning the filter
+    pub fn into_inner(self) -> F {
+        self.filter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AccuracyTracker;
+    use crate::QuotientHashTable;
+
+    #[test]
+    fn time_jumps_take_no_memory() {
+        let mut tracker =
+            AccuracyTracker::new(QuotientHashTable::new(1024, 4, 8)).with_time_period(1);
+        tracker.insert_at(1, 0);
+        tracker.insert_at(2, 3);
+        tracker.insert_at(1, u64::max_value());
+        tracker.insert_at(3, u64::max_value());
+
+        assert_eq!(tracker.windows().len(), 2);
+        assert_eq!(tracker.skipped_periods(), u64::max_value() - 2);
+        assert_eq!(tracker.current().total(), 2);
+        assert_eq!(tracker.total().true_positives, 1);
+
+        // Earlier timestamps fall in the current period
+        tracker.insert_at(4, 5);
+        assert_eq!(tracker.windows().len(), 2);
+        assert_eq!(tracker.current().total(), 3);
+    }
+}
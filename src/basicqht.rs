@@ -1,6 +1,5 @@
 use crate::filter::Filter;
-pub use std::collections::hash_map::DefaultHasher;
-pub use std::hash::{Hash, Hasher};
+use core::hash::{BuildHasher, Hash, Hasher};
 
 pub type Fingerprint = u64;
 
@@ -25,64 +24,100 @@ pub trait BasicQHT: Filter {
 }
 
 /// Returns the hash of (e, base, counter)
-pub fn get_hash(e: impl Hash, base: u64, counter: u64) -> u64 {
-    let mut s = DefaultHasher::new();
+pub fn get_hash(hasher: &impl BuildHasher, e: impl Hash, base: u64, counter: u64) -> u64 {
+    let mut s = hasher.build_hasher();
     e.hash(&mut s);
     base.hash(&mut s);
     counter.hash(&mut s);
-    s.finish()
+    mix(s.finish())
 }
 
-#[macro_export]
-macro_rules! impl_basicqht {
-    ($struct_type:ty) => {
-        impl BasicQHT for $struct_type {
-            /// Retrieves a fingerprint from a given bucket (provided as an `address` and `bucket_number`)
-            fn get_fingerprint_from_bucket(
-                &self,
-                address: usize,
-                bucket_number: usize,
-            ) -> Fingerprint {
-                let offset = (address * self.n_buckets + bucket_number) * self.fingerprint_size;
-
-                self.qht.extract_u64(offset, self.fingerprint_size)
-            }
-
-            /// Inserts a fingerprint in a given buffer (provided as an `address` and `bucket_number`)
-            fn insert_fingerprint_in_bucket(
-                &mut self,
-                address: usize,
-                bucket_number: usize,
-                fingerprint: Fingerprint,
-            ) {
-                let offset = (address * self.n_buckets + bucket_number) * self.fingerprint_size;
-
-                self.qht
-                    .insert_u64(fingerprint, offset, self.fingerprint_size);
-            }
-
-            /// Checks whether a fingerprint belongs to a given cell
-            fn in_cell(&self, address: usize, fingerprint: Fingerprint) -> bool {
-                for idx in 0..self.n_buckets {
-                    if self.get_fingerprint_from_bucket(address, idx) == fingerprint {
-                        return true;
-                    }
-                }
-                false
-            }
-
-            /// Obtains an element's fingerprint
-            fn get_fingerprint(&self, e: impl Hash) -> Fingerprint {
-                let mut fingerprint = 0;
-                let mut counter = 0;
-
-                while fingerprint == 0 {
-                    let v = get_hash(&e, 2, counter);
-                    fingerprint = (v % self.pow_fingerprint_size) as Fingerprint;
-                    counter += 1;
-                }
-                fingerprint
-            }
-        }
-    };
+/// SplitMix64 finalizer
+///
+/// Fingerprints are taken from the lowest bits of hashes: these are mixed with the highest ones,
+/// which weak hashers such as `FnvHasher` leave apart.
+pub fn mix(hash: u64) -> u64 {
+    let mut z = hash;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Returns the 64-bit hash of `e`, from which its address and fingerprint are derived
+pub fn hash_of(hasher: &impl BuildHasher, e: impl Hash) -> u64 {
+    get_hash(hasher, e, 1, 0)
+}
+
+/// Returns the (non-zero) fingerprint held by the lowest bits of `hash`, `pow_fingerprint_size`
+/// being 2^`fingerprint_size`
+///
+/// If these bits are all zero, they are taken from `rehash(hash, 1)`, `rehash(hash, 2)`, ... instead.
+pub fn fingerprint_of(hash: u64, pow_fingerprint_size: u64) -> Fingerprint {
+    let mut fingerprint = hash & (pow_fingerprint_size - 1);
+    let mut counter = 0;
+
+    while fingerprint == 0 {
+        counter += 1;
+        fingerprint = rehash(hash, counter) & (pow_fingerprint_size - 1);
+    }
+    fingerprint
+}
+
+/// Returns the address in `[0, n_cells)` held by the highest bits of `hash`
+///
+/// This is a multiply-shift range reduction, which avoids a division; when `n_cells` is a power of
+/// two, it amounts to keeping the log2(`n_cells`) highest bits of `hash`.
+pub fn address_of(hash: u64, n_cells: usize) -> usize {
+    ((u128::from(hash) * n_cells as u128) >> 64) as usize
+}
+
+/// Folds a 128-bit hash into the 64-bit hash addressing its element
+pub fn fold_hash(hash: u128) -> u64 {
+    (hash >> 64) as u64 ^ hash as u64
+}
+
+/// Derives another well-mixed hash from `hash`, distinct for each `counter`
+///
+/// Used for the addresses of the candidate cells after the first one, and for fingerprint retries.
+pub fn rehash(hash: u64, counter: u64) -> u64 {
+    mix(hash.wrapping_add(counter.wrapping_mul(0x9e37_79b9_7f4a_7c15)))
+}
+
+/// Returns the hash whose highest bits give the `choice`-th candidate cell of an element of hash `hash`
+pub fn candidate_hash(hash: u64, choice: usize) -> u64 {
+    if choice == 0 {
+        hash
+    } else {
+        rehash(hash, choice as u64)
+    }
+}
+
+/// Largest supported fingerprint size, in bits
+pub const FINGERPRINT_SIZE_LIMIT: usize = 8;
+
+/// Panics if the provided parameters cannot describe a QHT
+pub fn check_parameters(n_buckets: usize, fingerprint_size: usize) {
+    // Fingerprint size is limited
+    if fingerprint_size > FINGERPRINT_SIZE_LIMIT {
+        panic!("[QHTc Filter] Incorrect parameters, fingerprint_size cannot exceed 8.");
+    } else if fingerprint_size == 0 {
+        panic!("[QHTc Filter] Incorrect parameters, fingerprint_size cannot be zero.");
+    }
+
+    // At least one bucket is required
+    if n_buckets == 0 {
+        panic!("[QHTc Filter] Incorrect parameters, n_buckets cannot be zero.");
+    }
+}
+
+/// Returns the number of cells fitting in `memory_size` bits, or panics
+pub fn cells_for_memory(memory_size: usize, n_buckets: usize, fingerprint_size: usize) -> usize {
+    check_parameters(n_buckets, fingerprint_size);
+    let n_cells = memory_size / (n_buckets * fingerprint_size);
+
+    // There should be at least one cell
+    if n_cells == 0 {
+        panic!("[QHT Filter] Incorrect parameters, memory size should be at least n_buckets * fingerprint_size");
+    }
+    n_cells
 }
@@ -0,0 +1,312 @@
+use crate::basicqht::{address_of, candidate_hash, hash_of};
+use crate::filter::{DynFilter, Filter};
+use crate::hash::DefaultBuildHasher;
+use crate::storage::{CellStorage, DefaultStorage, OwnedCellStorage};
+
+use core::hash::{BuildHasher, Hash};
+use rand::rngs::StdRng;
+#[cfg(feature = "std")]
+use rand::FromEntropy;
+use rand::Rng;
+
+/// Largest supported cell size of a `StableBloomFilter`, in bits
+pub const STABLE_CELL_SIZE_LIMIT: usize = 8;
+
+/// Panics if `n_cells` cells cannot hold a filter using `n_hashes` hash functions
+fn check_bloom_parameters(n_cells: usize, n_hashes: usize) {
+    if n_hashes == 0 {
+        panic!("[Bloom Filter] Incorrect parameters, n_hashes cannot be zero.");
+    }
+    if n_cells == 0 {
+        panic!("[Bloom Filter] Incorrect parameters, memory size should hold at least one cell.");
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Bloom filter
+
+/// Bloom filter
+///
+/// Each element sets `n_hashes` bits among `memory_size`, derived from its hash as the addresses
+/// of QHT's candidate cells. It has no false negatives, but fills up: its false positive rate
+/// tends to 1 on unbounded streams. Provided as a baseline.
+///
+/// # Example
+/// ```rust
+/// use qht::{BloomFilter, Element, Filter};
+/// let mut f = BloomFilter::new(1024, 3);
+/// let e = Element { value: 1234 };
+/// assert!( !f.insert(e) ); // The filter did not previously contain e
+/// assert!( f.lookup(e) );
+/// ```
+pub struct BloomFilter<S = DefaultStorage, H = DefaultBuildHasher> {
+    /// Number of bits
+    n_bits: usize,
+
+    /// Number of bits set per element
+    n_hashes: usize,
+
+    /// Underlying data structure
+    bits: S,
+
+    /// Hash function
+    hasher: H,
+}
+
+#[cfg(feature = "std")]
+impl BloomFilter {
+    /// Returns a newly created `BloomFilter` or panics
+    ///
+    /// This function takes as arguments:
+    /// * `memory_size`: allocated memory for the filter, in bits
+    /// * `n_hashes`: number of bits set per element
+    pub fn new(memory_size: usize, n_hashes: usize) -> Self {
+        Self::with_hasher(memory_size, n_hashes, DefaultBuildHasher::default())
+    }
+}
+
+impl<S: OwnedCellStorage, H: BuildHasher> BloomFilter<S, H> {
+    /// Returns a newly created `BloomFilter` using the provided hasher, or panics
+    ///
+    /// Other arguments are the same as for `BloomFilter::new`.
+    pub fn with_hasher(memory_size: usize, n_hashes: usize, hasher: H) -> Self {
+        check_bloom_parameters(memory_size, n_hashes);
+        Self {
+            n_bits: memory_size,
+            n_hashes,
+            bits: S::with_buckets(memory_size, 1),
+            hasher,
+        }
+    }
+}
+
+impl<S: CellStorage, H: BuildHasher> BloomFilter<S, H> {
+    /// Performs a lookup for the element of hash `hash`
+    pub fn lookup_hash(&self, hash: u64) -> bool {
+        (0..self.n_hashes).all(|idx| self.bits.get_bucket(self.position(hash, idx), 1) != 0)
+    }
+
+    /// Inserts the element of hash `hash`, returning whether it was previously present
+    pub fn insert_hash(&mut self, hash: u64) -> bool {
+        let mut present = true;
+        for idx in 0..self.n_hashes {
+            let position = self.position(hash, idx);
+            if self.bits.get_bucket(position, 1) == 0 {
+                present = false;
+                self.bits.set_bucket(position, 1, 1);
+            }
+        }
+        present
+    }
+
+    /// Returns the `idx`-th bit of the element of hash `hash`
+    fn position(&self, hash: u64, idx: usize) -> usize {
+        address_of(candidate_hash(hash, idx), self.n_bits)
+    }
+}
+
+impl<S: CellStorage, H: BuildHasher> Filter for BloomFilter<S, H> {
+    /// Performs a lookup for the provided element
+    fn lookup(&self, e: impl Hash) -> bool {
+        self.lookup_hash(hash_of(&self.hasher, e))
+    }
+
+    /// Inserts an element, returning whether it was previously present
+    fn insert(&mut self, e: impl Hash) -> bool {
+        let hash = hash_of(&self.hasher, e);
+        self.insert_hash(hash)
+    }
+}
+
+impl<S: CellStorage, H: BuildHasher> DynFilter for BloomFilter<S, H> {
+    fn lookup_hash(&self, hash: u64) -> bool {
+        BloomFilter::lookup_hash(self, hash)
+    }
+
+    fn insert_hash(&mut self, hash: u64) -> bool {
+        BloomFilter::insert_hash(self, hash)
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Stable Bloom filter
+
+/// Stable Bloom Filter (Deng and Rafiei, 2006)
+///
+/// A Bloom filter of counters of `cell_size` bits: inserting an element first decrements
+/// `n_decrements` consecutive cells from a random position, then sets its `n_hashes` cells to the
+/// maximum. Old elements thus fade out, and the false positive rate converges to a constant
+/// instead of tending to 1, at the cost of false negatives. Provided as a baseline.
+///
+/// # Example
+/// ```rust
+/// use qht::{Element, Filter, StableBloomFilter};
+/// let mut f = StableBloomFilter::new(1024, 2, 3, 1);
+/// let e = Element { value: 1234 };
+/// assert!( !f.insert(e) ); // The filter did not previously contain e
+/// assert!( f.lookup(e) );
+/// ```
+pub struct StableBloomFilter<S = DefaultStorage, H = DefaultBuildHasher> {
+    /// Number of cells (automatically computed)
+    n_cells: usize,
+
+    /// Size of the cells (in bits)
+    cell_size: usize,
+
+    /// Number of cells set per element
+    n_hashes: usize,
+
+    /// Number of cells decremented per insertion
+    n_decrements: usize,
+
+    /// Underlying data structure
+    cells: S,
+
+    /// Hash function
+    hasher: H,
+
+    /// Random number generator
+    rng: StdRng,
+}
+
+#[cfg(feature = "std")]
+impl StableBloomFilter {
+    /// Returns a newly created `StableBloomFilter` or panics
+    ///
+    /// This function takes as arguments:
+    /// * `memory_size`: allocated memory for the filter, in bits
+    /// * `cell_size`: size of each cell, in bits. Cannot exceed `STABLE_CELL_SIZE_LIMIT`.
+    /// * `n_hashes`: number of cells set per element
+    /// * `n_decrements`: number of cells decremented per insertion; the more, the faster
+    ///   elements are forgotten
+    pub fn new(memory_size: usize, cell_size: usize, n_hashes: usize, n_decrements: usize) -> Self {
+        Self::with_hasher(
+            memory_size,
+            cell_size,
+            n_hashes,
+            n_decrements,
+            DefaultBuildHasher::default(),
+            StdRng::from_entropy(),
+        )
+    }
+}
+
+impl<S: OwnedCellStorage, H: BuildHasher> StableBloomFilter<S, H> {
+    /// Returns a newly created `StableBloomFilter` using the provided hasher and random number
+    /// generator, or panics
+    ///
+    /// Other arguments are the same as for `StableBloomFilter::new`.
+    pub fn with_hasher(
+        memory_size: usize,
+        cell_size: usize,
+        n_hashes: usize,
+        n_decrements: usize,
+        hasher: H,
+        rng: StdRng,
+    ) -> Self {
+        // Cell size is limited
+        if cell_size > STABLE_CELL_SIZE_LIMIT {
+            panic!("[Stable Bloom Filter] Incorrect parameters, cell_size cannot exceed 8.");
+        } else if cell_size == 0 {
+            panic!("[Stable Bloom Filter] Incorrect parameters, cell_size cannot be zero.");
+        }
+
+        let n_cells = memory_size / cell_size;
+        check_bloom_parameters(n_cells, n_hashes);
+
+        Self {
+            n_cells,
+            cell_size,
+            n_hashes,
+            n_decrements,
+            cells: S::with_buckets(n_cells, cell_size),
+            hasher,
+            rng,
+        }
+    }
+}
+
+impl<S: CellStorage, H: BuildHasher> StableBloomFilter<S, H> {
+    /// Performs a lookup for the element of hash `hash`
+    pub fn lookup_hash(&self, hash: u64) -> bool {
+        (0..self.n_hashes).all(|idx| {
+            let position = address_of(candidate_hash(hash, idx), self.n_cells);
+            self.cells.get_bucket(position, self.cell_size) != 0
+        })
+    }
+
+    /// Inserts the element of hash `hash`, returning whether it was previously present
+    pub fn insert_hash(&mut self, hash: u64) -> bool {
+        let present = self.lookup_hash(hash);
+
+        // Consecutive cells are decremented, as in the reference implementation
+        let start = self.rng.gen_range(0, self.n_cells);
+        for offset in 0..self.n_decrements {
+            let position = (start + offset) % self.n_cells;
+            let value = self.cells.get_bucket(position, self.cell_size);
+            if value > 0 {
+                self.cells.set_bucket(position, self.cell_size, value - 1);
+            }
+        }
+
+        let max = (1 << self.cell_size) - 1;
+        for idx in 0..self.n_hashes {
+            let position = address_of(candidate_hash(hash, idx), self.n_cells);
+            self.cells.set_bucket(position, self.cell_size, max);
+        }
+        present
+    }
+}
+
+impl<S: CellStorage, H: BuildHasher> Filter for StableBloomFilter<S, H> {
+    /// Performs a lookup for the provided element
+    fn lookup(&self, e: impl Hash) -> bool {
+        self.lookup_hash(hash_of(&self.hasher, e))
+    }
+
+    /// Inserts an element, returning whether it was previously present
+    fn insert(&mut self, e: impl Hash) -> bool {
+        let hash = hash_of(&self.hasher, e);
+        self.insert_hash(hash)
+    }
+}
+
+impl<S: CellStorage, H: BuildHasher> DynFilter for StableBloomFilter<S, H> {
+    fn lookup_hash(&self, hash: u64) -> bool {
+        StableBloomFilter::lookup_hash(self, hash)
+    }
+
+    fn insert_hash(&mut self, hash: u64) -> bool {
+        StableBloomFilter::insert_hash(self, hash)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{BloomFilter, StableBloomFilter};
+    use crate::testing::last_error_rates;
+    use crate::{streams, QuotientHashTable};
+
+    #[test]
+    fn stable_bloom_filter_stays_usable() {
+        // Far more distinct elements than the filters can hold
+        let stream: Vec<u64> = streams::DelayedDuplicates::new(100, 0.5, 23)
+            .take(200_000)
+            .collect();
+        let memory = 40_000;
+
+        let bloom = last_error_rates(BloomFilter::new(memory, 4), &stream);
+        let stable = last_error_rates(StableBloomFilter::new(memory, 2, 4, 10), &stream);
+        let qht = last_error_rates(QuotientHashTable::new(memory, 4, 8), &stream);
+
+        // A saturated Bloom filter flags everything
+        assert!(bloom.0 > 0.99);
+        assert_eq!(bloom.1, 0.0);
+
+        // Forgetting filters catch recent duplicates while staying selective
+        assert!(stable.0 < 0.2);
+        assert!(stable.1 < 0.2);
+        assert!(qht.0 < 0.2);
+        assert!(qht.1 < 0.2);
+    }
+}
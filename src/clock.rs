@@ -0,0 +1,94 @@
+use crate::basicqht::Fingerprint;
+use crate::format::Flavour;
+use crate::hash::DefaultBuildHasher;
+use crate::policy::InsertPolicy;
+use crate::storage::{CellStorage, DefaultStorage};
+use crate::table::{Cell, Qht};
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+// --------------------------------------------------------------------------------
+
+/// CLOCK Quotient Hash Table ("compact")
+///
+/// Fingerprints that are seen again get a second chance before being evicted, which approximates
+/// `LruQuotientHashTable` without shifting the cell on every insertion.
+///
+/// # Example
+/// ```rust
+/// use qht::{ClockQuotientHashTable, Element, Filter};
+/// let mut f = ClockQuotientHashTable::new(1024, 4, 3);
+/// let e = Element { value: 1234 };
+/// assert!( !f.insert(e) ); // The filter did not previously contain e
+/// assert!( f.insert(e) ); // Marks e as referenced
+/// assert!( f.lookup(e) );
+/// ```
+pub type ClockQuotientHashTable<S = DefaultStorage, H = DefaultBuildHasher> = Qht<Clock, S, H>;
+
+/// Insertion policy of `ClockQuotientHashTable`
+///
+/// Each bucket has a reference bit, set when its fingerprint is seen again, and each cell a hand.
+/// On a miss, the fingerprint goes to the first empty bucket; if the cell is full, the hand sweeps
+/// the cell, clearing reference bits, until it reaches an unreferenced bucket, which is evicted.
+///
+/// This costs one bit per bucket and 32 bits per cell, held next to the storage.
+#[derive(Clone, Debug, Default)]
+pub struct Clock {
+    /// Reference bits, one per bucket
+    referenced: Vec<u64>,
+
+    /// Position of the hand, one per cell
+    hands: Vec<u32>,
+}
+
+impl Clock {
+    fn is_referenced(&self, bucket: usize) -> bool {
+        self.referenced[bucket / 64] & (1 << (bucket % 64)) != 0
+    }
+
+    fn set_referenced(&mut self, bucket: usize, referenced: bool) {
+        if referenced {
+            self.referenced[bucket / 64] |= 1 << (bucket % 64);
+        } else {
+            self.referenced[bucket / 64] &= !(1 << (bucket % 64));
+        }
+    }
+}
+
+impl InsertPolicy for Clock {
+    const FLAVOUR: Flavour = Flavour::Clock;
+
+    fn init(&mut self, n_cells: usize, n_buckets: usize) {
+        self.referenced = vec![0; (n_cells * n_buckets + 63) / 64];
+        self.hands = vec![0; n_cells];
+    }
+
+    fn insert<S: CellStorage>(
+        &mut self,
+        cell: &mut Cell<S>,
+        fingerprint: Fingerprint,
+        position: Option<usize>,
+    ) -> Option<usize> {
+        let first = cell.address() * cell.n_buckets();
+        if let Some(bucket) = position {
+            self.set_referenced(first + bucket, true);
+            return position;
+        }
+
+        // Newly inserted fingerprints are unreferenced, so that elements seen once go first
+        if let Some(bucket) = cell.insert_empty(fingerprint) {
+            self.set_referenced(first + bucket, false);
+            return Some(bucket);
+        }
+
+        let mut hand = self.hands[cell.address()] as usize;
+        while self.is_referenced(first + hand) {
+            self.set_referenced(first + hand, false);
+            hand = (hand + 1) % cell.n_buckets();
+        }
+        cell.set(hand, fingerprint);
+        self.hands[cell.address()] = ((hand + 1) % cell.n_buckets()) as u32;
+        Some(hand)
+    }
+}
@@ -0,0 +1,245 @@
+use crate::bloom::{BloomFilter, StableBloomFilter};
+use crate::clock::ClockQuotientHashTable;
+use crate::counting::CountingQuotientHashTable;
+use crate::cuckoo::CuckooFilter;
+use crate::double_buffer::DoubleBufferFilter;
+use crate::filter::DynFilter;
+use crate::format::Flavour;
+use crate::lru::LruQuotientHashTable;
+use crate::qht::QuotientHashTable;
+use crate::qqht::QQuotientHashTable;
+use crate::qqhtd::QQuotientHashTableD;
+
+// --------------------------------------------------------------------------------
+// Runtime filter selection
+
+/// Description of a filter, from which `build` creates it
+///
+/// Arguments are those of the constructors of the corresponding filters; memory sizes are in bits.
+///
+/// # Example
+/// ```rust
+/// use qht::format::Flavour;
+/// use qht::FilterConfig;
+/// let config = FilterConfig::DoubleBuffer {
+///     capacity: 100,
+///     inner: Box::new(FilterConfig::Qht {
+///         flavour: Flavour::QQhtD,
+///         memory_size: 1024,
+///         n_buckets: 4,
+///         fingerprint_size: 8,
+///         choices: 1,
+///     }),
+/// };
+/// let mut f = config.build();
+/// assert!( !f.insert_key(b"record") );
+/// assert!( f.insert_key(b"record") );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum FilterConfig {
+    /// A QHT of the given flavour, which cannot be `Flavour::Custom`
+    Qht {
+        flavour: Flavour,
+        memory_size: usize,
+        n_buckets: usize,
+        fingerprint_size: usize,
+        choices: usize,
+    },
+
+    /// A `CountingQuotientHashTable`
+    Counting {
+        memory_size: usize,
+        n_buckets: usize,
+        fingerprint_size: usize,
+        counter_size: usize,
+    },
+
+    /// A `BloomFilter`
+    Bloom { memory_size: usize, n_hashes: usize },
+
+    /// A `StableBloomFilter`
+    StableBloom {
+        memory_size: usize,
+        cell_size: usize,
+        n_hashes: usize,
+        n_decrements: usize,
+    },
+
+    /// A `CuckooFilter`
+    Cuckoo {
+        memory_size: usize,
+        n_buckets: usize,
+        fingerprint_size: usize,
+        max_kicks: usize,
+        evict_on_failure: bool,
+    },
+
+    /// A `DoubleBufferFilter` rotating filters described by `inner`
+    DoubleBuffer {
+        capacity: usize,
+        inner: Box<FilterConfig>,
+    },
+}
+
+impl FilterConfig {
+    /// Returns a newly created filter, or panics if the description is inconsistent
+    pub fn build(&self) -> Box<dyn DynFilter> {
+        match *self {
+            FilterConfig::Qht {
+                flavour,
+                memory_size,
+                n_buckets,
+                fingerprint_size,
+                choices,
+            } => {
+                let (m, b, f) = (memory_size, n_buckets, fingerprint_size);
+                match flavour {
+                    Flavour::Qht => Box::new(QuotientHashTable::new(m, b, f).with_choices(choices)),
+                    Flavour::QQht => {
+                        Box::new(QQuotientHashTable::new(m, b, f).with_choices(choices))
+                    }
+                    Flavour::QQhtD => {
+                        Box::new(QQuotientHashTableD::new(m, b, f).with_choices(choices))
+                    }
+                    Flavour::Lru => {
+                        Box::new(LruQuotientHashTable::new(m, b, f).with_choices(choices))
+                    }
+                    Flavour::Clock => {
+                        Box::new(ClockQuotientHashTable::new(m, b, f).with_choices(choices))
+                    }
+                    Flavour::Custom => {
+                        panic!(
+                            "[FilterConfig] Incorrect parameters, custom flavours cannot be built."
+                        )
+                    }
+                }
+            }
+            FilterConfig::Counting {
+                memory_size,
+                n_buckets,
+                fingerprint_size,
+                counter_size,
+            } => Box::new(CountingQuotientHashTable::new(
+                memory_size,
+                n_buckets,
+                fingerprint_size,
+                counter_size,
+            )),
+            FilterConfig::Bloom {
+                memory_size,
+                n_hashes,
+            } => Box::new(BloomFilter::new(memory_size, n_hashes)),
+            FilterConfig::StableBloom {
+                memory_size,
+                cell_size,
+                n_hashes,
+                n_decrements,
+            } => Box::new(StableBloomFilter::new(
+                memory_size,
+                cell_size,
+                n_hashes,
+                n_decrements,
+            )),
+            FilterConfig::Cuckoo {
+                memory_size,
+                n_buckets,
+                fingerprint_size,
+                max_kicks,
+                evict_on_failure,
+            } => {
+                let f = CuckooFilter::new(memory_size, n_buckets, fingerprint_size)
+                    .with_max_kicks(max_kicks);
+                if evict_on_failure {
+                    Box::new(f.evict_on_failure())
+                } else {
+                    Box::new(f)
+                }
+            }
+            FilterConfig::DoubleBuffer {
+                capacity,
+                ref inner,
+            } => {
+                let inner = inner.clone();
+                Box::new(DoubleBufferFilter::new(capacity, move || inner.build()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FilterConfig;
+    use crate::format::Flavour;
+    use crate::testing::last_error_rates;
+    use crate::{hash, streams, BloomFilter, DynFilter, Filter};
+
+    #[test]
+    fn configured_filters() {
+        let (memory_size, n_buckets, fingerprint_size) = (40_000, 4, 8);
+        let qht = |flavour| FilterConfig::Qht {
+            flavour,
+            memory_size,
+            n_buckets,
+            fingerprint_size,
+            choices: 2,
+        };
+        let configs = vec![
+            qht(Flavour::Qht),
+            qht(Flavour::QQht),
+            qht(Flavour::QQhtD),
+            qht(Flavour::Lru),
+            qht(Flavour::Clock),
+            FilterConfig::Counting {
+                memory_size,
+                n_buckets,
+                fingerprint_size: 6,
+                counter_size: 2,
+            },
+            FilterConfig::StableBloom {
+                memory_size,
+                cell_size: 2,
+                n_hashes: 4,
+                n_decrements: 10,
+            },
+            FilterConfig::Cuckoo {
+                memory_size,
+                n_buckets,
+                fingerprint_size,
+                max_kicks: 20,
+                evict_on_failure: true,
+            },
+            FilterConfig::DoubleBuffer {
+                capacity: 2000,
+                inner: Box::new(FilterConfig::Bloom {
+                    memory_size: memory_size / 2,
+                    n_hashes: 7,
+                }),
+            },
+        ];
+
+        // Filters chosen at runtime go through the same harness
+        let stream: Vec<u64> = streams::DelayedDuplicates::new(100, 0.5, 37)
+            .take(20_000)
+            .collect();
+        for config in &configs {
+            let (fpr, fnr) = last_error_rates(config.build(), &stream);
+            assert!(fpr < 0.2, "{:?}", config);
+            assert!(fnr < 0.2, "{:?}", config);
+        }
+
+        // Boxed filters hash elements and keys as the static ones
+        let mut boxed = FilterConfig::Bloom {
+            memory_size,
+            n_hashes: 3,
+        }
+        .build();
+        let mut bloom = BloomFilter::new(memory_size, 3);
+        for e in streams::Uniform::new(50_000, 41).take(10_000) {
+            assert_eq!(boxed.insert(e), bloom.insert(e));
+            assert_eq!(
+                boxed.insert_key(&e.to_le_bytes()),
+                bloom.insert_hash(hash::key_hash(e.to_le_bytes()))
+            );
+        }
+    }
+}
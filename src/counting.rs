@@ -0,0 +1,252 @@
+use crate::basicqht::*;
+use crate::filter::{DynFilter, Filter};
+use crate::hash::{key_hash, DefaultBuildHasher};
+use crate::policy::InsertPolicy;
+use crate::qht::RandomEviction;
+use crate::storage::{CellStorage, DefaultStorage, OwnedCellStorage};
+use crate::table::Qht;
+
+use core::hash::{BuildHasher, Hash};
+
+/// Largest supported counter size, in bits
+pub const COUNTER_SIZE_LIMIT: usize = 32;
+
+// --------------------------------------------------------------------------------
+
+/// Counting Quotient Hash Table ("compact")
+///
+/// Each bucket holds a fingerprint and a saturating counter of `counter_size` bits, estimating how
+/// many times the element was inserted. Addressing, fingerprints and cells are those of `Qht`;
+/// by default, a random bucket is evicted when a cell is full (see `RandomEviction`).
+///
+/// Counts are approximate: fingerprint collisions add up, and evicted elements start over from zero.
+///
+/// # Example
+/// ```rust
+/// use qht::{CountingQuotientHashTable, Element};
+/// let mut f = CountingQuotientHashTable::new(1024, 4, 6, 2);
+/// let e = Element { value: 1234 };
+/// assert_eq!( f.insert(e), 0 ); // The filter did not previously contain e
+/// assert_eq!( f.insert(e), 1 );
+/// assert_eq!( f.count(e), 2 );
+///
+/// for _ in 0..10 {
+///     f.insert(e);
+/// }
+/// assert_eq!( f.count(e), 3 ); // The counter saturates
+/// ```
+pub struct CountingQuotientHashTable<P = RandomEviction, S = DefaultStorage, H = DefaultBuildHasher>
+{
+    /// Underlying table, storing counters after fingerprints
+    table: Qht<P, S, H>,
+
+    /// Size of the counter (in bits)
+    counter_size: usize,
+}
+
+#[cfg(feature = "std")]
+impl CountingQuotientHashTable {
+    /// Returns a newly created `CountingQuotientHashTable` or panics
+    ///
+    /// This function takes as arguments:
+    /// * `memory_size`: allocated memory for the filter, in bits
+    /// * `n_buckets`: number of buckets
+    /// * `fingerprint_size`: size of each fingerprint, in bits. Cannot exceed `FINGERPRINT_SIZE_LIMIT`.
+    /// * `counter_size`: size of each counter, in bits. Cannot exceed `COUNTER_SIZE_LIMIT`.
+    ///
+    /// Parameters should be chosen in a consistent way, namely so that
+    /// `memory_size` >= `n_buckets` * (`fingerprint_size` + `counter_size`)
+    pub fn new(
+        memory_size: usize,
+        n_buckets: usize,
+        fingerprint_size: usize,
+        counter_size: usize,
+    ) -> Self {
+        Self::with_hasher(
+            memory_size,
+            n_buckets,
+            fingerprint_size,
+            counter_size,
+            DefaultBuildHasher::default(),
+            RandomEviction::default(),
+        )
+    }
+}
+
+impl<P: InsertPolicy, S: OwnedCellStorage, H: BuildHasher> CountingQuotientHashTable<P, S, H> {
+    /// Returns a newly created `CountingQuotientHashTable` using the provided hasher and policy, or panics
+    ///
+    /// Other arguments are the same as for `CountingQuotientHashTable::new`.
+    pub fn with_hasher(
+        memory_size: usize,
+        n_buckets: usize,
+        fingerprint_size: usize,
+        counter_size: usize,
+        hasher: H,
+        policy: P,
+    ) -> Self {
+        check_parameters(n_buckets, fingerprint_size);
+
+        // Counter size is limited
+        if counter_size > COUNTER_SIZE_LIMIT {
+            panic!("[CQHTc Filter] Incorrect parameters, counter_size cannot exceed 32.");
+        } else if counter_size == 0 {
+            panic!("[CQHTc Filter] Incorrect parameters, counter_size cannot be zero.");
+        }
+
+        let n_cells = memory_size / (n_buckets * (fingerprint_size + counter_size));
+
+        // There should be at least one cell
+        if n_cells == 0 {
+            panic!("[CQHTc Filter] Incorrect parameters, memory size should be at least n_buckets * (fingerprint_size + counter_size)");
+        }
+
+        Self {
+            table: Qht::with_payload(
+                n_cells,
+                n_buckets,
+                fingerprint_size,
+                counter_size,
+                hasher,
+                policy,
+            ),
+            counter_size,
+        }
+    }
+}
+
+impl<P: InsertPolicy, S: CellStorage, H: BuildHasher> CountingQuotientHashTable<P, S, H> {
+    /// Gives each element `choices` candidate cells instead of one, or panics
+    ///
+    /// See `Qht::with_choices`.
+    pub fn with_choices(mut self, choices: usize) -> Self {
+        self.table = self.table.with_choices(choices);
+        self
+    }
+
+    /// Returns how many times `e` was inserted, up to the counter's capacity
+    pub fn count(&self, e: impl Hash) -> u64 {
+        self.count_hash(self.table.hash(e))
+    }
+
+    /// Inserts `e`, incrementing its counter
+    ///
+    /// Returns how many times `e` was previously inserted, up to the counter's capacity
+    pub fn insert(&mut self, e: impl Hash) -> u64 {
+        let hash = self.table.hash(e);
+        self.insert_hash(hash)
+    }
+
+    /// Returns how many times the element of hash `hash` was inserted (see `Qht::lookup_hash`)
+    pub fn count_hash(&self, hash: u64) -> u64 {
+        self.table.payload(hash).unwrap_or(0)
+    }
+
+    /// Inserts the element of hash `hash`, incrementing its counter (see `Qht::insert_hash`)
+    ///
+    /// Returns how many times it was previously inserted, up to the counter's capacity
+    pub fn insert_hash(&mut self, hash: u64) -> u64 {
+        let max_count = (1 << self.counter_size) - 1;
+        self.table
+            .insert_payload(hash, |count| match count {
+                Some(count) if count == max_count => count,
+                Some(count) => count + 1,
+                None => 1,
+            })
+            .unwrap_or(0)
+    }
+
+    /// Returns how many times a byte-string key was inserted (see `Qht::lookup_key`)
+    pub fn count_key(&self, key: impl AsRef<[u8]>) -> u64 {
+        self.count_hash(key_hash(key))
+    }
+
+    /// Inserts a byte-string key, incrementing its counter
+    ///
+    /// See `count_key` and `insert`.
+    pub fn insert_key(&mut self, key: impl AsRef<[u8]>) -> u64 {
+        self.insert_hash(key_hash(key))
+    }
+
+    /// Returns how many times the element of 128-bit hash `hash` was inserted (see
+    /// `Qht::lookup_hash128`)
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::CountingQuotientHashTable;
+    /// let mut f = CountingQuotientHashTable::new(1024, 4, 8, 4);
+    /// let hash = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210;
+    /// f.insert_hash128(hash);
+    /// assert_eq!( f.insert_hash128(hash), 1 );
+    /// assert_eq!( f.count_hash128(hash), 2 );
+    /// ```
+    pub fn count_hash128(&self, hash: u128) -> u64 {
+        self.count_hash(fold_hash(hash))
+    }
+
+    /// Inserts the element of 128-bit hash `hash`, incrementing its counter
+    ///
+    /// See `count_hash128` and `insert`.
+    pub fn insert_hash128(&mut self, hash: u128) -> u64 {
+        self.insert_hash(fold_hash(hash))
+    }
+
+    /// Returns the insertion policy
+    pub fn policy(&self) -> &P {
+        self.table.policy()
+    }
+}
+
+impl<P: InsertPolicy, S: CellStorage, H: BuildHasher> Filter
+    for CountingQuotientHashTable<P, S, H>
+{
+    /// Performs a lookup for the provided element
+    fn lookup(&self, e: impl Hash) -> bool {
+        self.count(e) > 0
+    }
+
+    /// Inserts an element, returning whether it was previously present
+    fn insert(&mut self, e: impl Hash) -> bool {
+        CountingQuotientHashTable::insert(self, e) > 0
+    }
+}
+
+impl<P: InsertPolicy, S: CellStorage, H: BuildHasher> DynFilter
+    for CountingQuotientHashTable<P, S, H>
+{
+    fn lookup_hash(&self, hash: u64) -> bool {
+        self.count_hash(hash) > 0
+    }
+
+    fn insert_hash(&mut self, hash: u64) -> bool {
+        CountingQuotientHashTable::insert_hash(self, hash) > 0
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::CountingQuotientHashTable;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashMap;
+
+    #[test]
+    fn counting_matches_exact_counts() {
+        // Few elements in a large filter, so that counts are exact. Wide cells are matched with SIMD
+        for &n_buckets in &[4, 32] {
+            let mut f = CountingQuotientHashTable::new(1_000_000, n_buckets, 8, 3);
+            let mut rng = StdRng::seed_from_u64(9);
+            let mut counts = HashMap::new();
+            for _ in 0..2000 {
+                let e = rng.gen_range(0, 100u64);
+                let count = counts.entry(e).or_insert(0);
+                assert_eq!(f.insert(e), std::cmp::min(*count, 7));
+                *count += 1;
+            }
+            for e in 0..200u64 {
+                let expected = counts.get(&e).map_or(0, |&c| std::cmp::min(c, 7));
+                assert_eq!(f.count(e), expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use crate::basicqht::*;
+use crate::filter::{DynFilter, Filter};
+use crate::hash::DefaultBuildHasher;
+use crate::storage::{CellStorage, DefaultStorage, OwnedCellStorage};
+use crate::table::{find_in_cell, Cell};
+
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash};
+use rand::rngs::StdRng;
+#[cfg(feature = "std")]
+use rand::FromEntropy;
+use rand::Rng;
+
+/// Number of relocations attempted by an insertion unless set with `with_max_kicks`
+pub const DEFAULT_MAX_KICKS: usize = 500;
+
+// --------------------------------------------------------------------------------
+
+/// Cuckoo filter (Fan et al., 2014)
+///
+/// Each element has a fingerprint, derived as in `QuotientHashTable`, and two candidate cells:
+/// the cell addressed by its hash, and an alternate one computed from this cell and the
+/// fingerprint alone. When both are full, fingerprints are kicked out to their alternate cell, up
+/// to `max_kicks` times.
+///
+/// When no room is found, the new element is dropped and the filter left unchanged. With
+/// `evict_on_failure`, the last kicked-out fingerprint is dropped instead, so that the filter keeps
+/// accepting new elements while forgetting old ones, as needed on unbounded streams.
+///
+/// # Example
+/// ```rust
+/// use qht::{CuckooFilter, Element, Filter};
+/// let mut f = CuckooFilter::new(1024, 4, 8).evict_on_failure();
+/// let e = Element { value: 1234 };
+/// assert!( !f.insert(e) ); // The filter did not previously contain e
+/// assert!( f.lookup(e) );
+/// ```
+pub struct CuckooFilter<S = DefaultStorage, H = DefaultBuildHasher> {
+    /// Number of cells (automatically computed)
+    n_cells: usize,
+
+    /// Number of buckets
+    n_buckets: usize,
+
+    /// Size of the fingerprint (in bits)
+    fingerprint_size: usize,
+
+    /// Size of the fingerprint (positional, automatically computed)
+    pow_fingerprint_size: u64,
+
+    /// Underlying data structure
+    table: S,
+
+    /// Hash function
+    hasher: H,
+
+    /// Random number generator
+    rng: StdRng,
+
+    /// Number of relocations attempted by an insertion
+    max_kicks: usize,
+
+    /// Whether a stored fingerprint is dropped when an insertion finds no room
+    evict_on_failure: bool,
+
+    /// Number of fingerprints dropped so far
+    dropped: u64,
+}
+
+#[cfg(feature = "std")]
+impl CuckooFilter {
+    /// Returns a newly created `CuckooFilter` or panics
+    ///
+    /// This function takes as arguments:
+    /// * `memory_size`: allocated memory for the filter, in bits
+    /// * `n_buckets`: number of buckets per cell
+    /// * `fingerprint_size`: size of each fingerprint, in bits. Cannot exceed `FINGERPRINT_SIZE_LIMIT`.
+    ///
+    /// Parameters should be chosen in a consistent way, namely so that
+    /// `memory_size` >= `n_buckets` * `fingerprint_size`
+    pub fn new(memory_size: usize, n_buckets: usize, fingerprint_size: usize) -> Self {
+        Self::with_hasher(
+            memory_size,
+            n_buckets,
+            fingerprint_size,
+            DefaultBuildHasher::default(),
+            StdRng::from_entropy(),
+        )
+    }
+}
+
+impl<S: OwnedCellStorage, H: BuildHasher> CuckooFilter<S, H> {
+    /// Returns a newly created `CuckooFilter` using the provided hasher and random number
+    /// generator, or panics
+    ///
+    /// Other arguments are the same as for `CuckooFilter::new`.
+    pub fn with_hasher(
+        memory_size: usize,
+        n_buckets: usize,
+        fingerprint_size: usize,
+        hasher: H,
+        rng: StdRng,
+    ) -> Self {
+        let n_cells = cells_for_memory(memory_size, n_buckets, fingerprint_size);
+        Self {
+            n_cells,
+            n_buckets,
+            fingerprint_size,
+            pow_fingerprint_size: 2u64.pow(fingerprint_size as u32),
+            table: S::with_buckets(n_cells * n_buckets, fingerprint_size),
+            hasher,
+            rng,
+            max_kicks: DEFAULT_MAX_KICKS,
+            evict_on_failure: false,
+            dropped: 0,
+        }
+    }
+}
+
+impl<S: CellStorage, H: BuildHasher> CuckooFilter<S, H> {
+    /// Sets the number of relocations attempted by an insertion before giving up (0 disables them)
+    pub fn with_max_kicks(mut self, max_kicks: usize) -> Self {
+        self.max_kicks = max_kicks;
+        self
+    }
+
+    /// Makes insertions finding no room drop a stored fingerprint rather than the new element
+    pub fn evict_on_failure(mut self) -> Self {
+        self.evict_on_failure = true;
+        self
+    }
+
+    /// Returns the number of fingerprints dropped by insertions finding no room
+    ///
+    /// These are the new elements, or the evicted ones with `evict_on_failure`.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Performs a lookup for the element of hash `hash`
+    pub fn lookup_hash(&self, hash: u64) -> bool {
+        let (address, fingerprint) = self.locate(hash);
+        self.in_cell(address, fingerprint)
+            || self.in_cell(self.alternate(address, fingerprint), fingerprint)
+    }
+
+    /// Performs a lookup for the element of hash `hash` and inserts it
+    ///
+    /// Returns whether it was previously present.
+    pub fn insert_hash(&mut self, hash: u64) -> bool {
+        let (first, fingerprint) = self.locate(hash);
+        let second = self.alternate(first, fingerprint);
+        if self.in_cell(first, fingerprint) || self.in_cell(second, fingerprint) {
+            return true;
+        }
+        if self.cell(first).insert_empty(fingerprint).is_some()
+            || self.cell(second).insert_empty(fingerprint).is_some()
+        {
+            return false;
+        }
+
+        // Both cells are full: kick fingerprints out to their alternate cell
+        let mut address = if self.rng.gen() { first } else { second };
+        let mut fingerprint = fingerprint;
+        let mut path = Vec::new();
+        for _ in 0..self.max_kicks {
+            let bucket = self.rng.gen_range(0, self.n_buckets);
+            let mut cell = self.cell(address);
+            let victim = cell.get(bucket);
+            cell.set(bucket, fingerprint);
+            path.push((address, bucket));
+
+            fingerprint = victim;
+            address = self.alternate(address, fingerprint);
+            if self.cell(address).insert_empty(fingerprint).is_some() {
+                return false;
+            }
+        }
+
+        // No room: the homeless fingerprint is dropped, unless kicks are undone to drop the new one
+        if !self.evict_on_failure {
+            for &(address, bucket) in path.iter().rev() {
+                let mut cell = self.cell(address);
+                let displaced = cell.get(bucket);
+                cell.set(bucket, fingerprint);
+                fingerprint = displaced;
+            }
+        }
+        self.dropped += 1;
+        false
+    }
+
+    /// Returns the first candidate cell and the fingerprint of the element of hash `hash`
+    fn locate(&self, hash: u64) -> (usize, Fingerprint) {
+        (
+            address_of(hash, self.n_cells),
+            fingerprint_of(hash, self.pow_fingerprint_size),
+        )
+    }
+
+    /// Returns the other candidate cell of `fingerprint`, stored in the cell at `address`
+    ///
+    /// This is an involution, (h(fingerprint) - address) mod n_cells, which does not require a power
+    /// of two cells.
+    fn alternate(&self, address: usize, fingerprint: Fingerprint) -> usize {
+        let offset = address_of(rehash(fingerprint, 0), self.n_cells);
+        (offset + self.n_cells - address) % self.n_cells
+    }
+
+    fn in_cell(&self, address: usize, fingerprint: Fingerprint) -> bool {
+        find_in_cell(
+            &self.table,
+            address * self.n_buckets,
+            self.n_buckets,
+            self.fingerprint_size,
+            self.pow_fingerprint_size - 1,
+            fingerprint,
+        )
+        .is_some()
+    }
+
+    fn cell(&mut self, address: usize) -> Cell<'_, S> {
+        Cell::new(
+            &mut self.table,
+            address,
+            self.n_buckets,
+            self.fingerprint_size,
+            self.pow_fingerprint_size - 1,
+        )
+    }
+}
+
+impl<S: CellStorage, H: BuildHasher> Filter for CuckooFilter<S, H> {
+    /// Performs a lookup for the provided element
+    fn lookup(&self, e: impl Hash) -> bool {
+        self.lookup_hash(hash_of(&self.hasher, e))
+    }
+
+    /// Inserts an element, returning whether it was previously present
+    fn insert(&mut self, e: impl Hash) -> bool {
+        let hash = hash_of(&self.hasher, e);
+        self.insert_hash(hash)
+    }
+}
+
+impl<S: CellStorage, H: BuildHasher> DynFilter for CuckooFilter<S, H> {
+    fn lookup_hash(&self, hash: u64) -> bool {
+        CuckooFilter::lookup_hash(self, hash)
+    }
+
+    fn insert_hash(&mut self, hash: u64) -> bool {
+        CuckooFilter::insert_hash(self, hash)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::CuckooFilter;
+    use crate::testing::last_error_rates;
+    use crate::{streams, Filter};
+
+    #[test]
+    fn cuckoo_filter_modes() {
+        // Elements stored without drops are never lost
+        let mut f = CuckooFilter::new(8000, 4, 8).with_max_kicks(50);
+        let mut stored = Vec::new();
+        for e in 0..1200u64 {
+            let dropped = f.dropped();
+            f.insert(e);
+            if f.dropped() == dropped {
+                stored.push(e);
+            }
+        }
+        assert!(f.dropped() > 0);
+        assert!(stored.len() > 900);
+        assert!(stored.iter().all(|&e| f.lookup(e)));
+
+        // Evicting filters keep catching recent duplicates on unbounded streams
+        let stream: Vec<u64> = streams::DelayedDuplicates::new(100, 0.5, 29)
+            .take(50_000)
+            .collect();
+        let evicting = last_error_rates(
+            CuckooFilter::new(40_000, 4, 8)
+                .with_max_kicks(20)
+                .evict_on_failure(),
+            &stream,
+        );
+        let strict = last_error_rates(CuckooFilter::new(40_000, 4, 8).with_max_kicks(20), &stream);
+        assert!(evicting.1 < 0.2);
+        assert!(strict.1 > 0.9);
+    }
+}
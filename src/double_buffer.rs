@@ -0,0 +1,165 @@
+use crate::filter::{DynFilter, Filter};
+
+use core::hash::Hash;
+use core::mem;
+
+// --------------------------------------------------------------------------------
+
+/// Double-buffer filter, as A² filters (Shen and Zhang, 2008)
+///
+/// Elements are inserted in an active filter, and looked up in both the active and the previous
+/// one. Once `capacity` elements were stored in the active filter, it becomes the previous one and
+/// a fresh filter, built by `new_filter`, becomes active. Elements are thus remembered for at
+/// least `capacity` and at most 2 * `capacity` distinct insertions, which gives an explicit
+/// forgetting horizon; duplicates found in the previous filter are refreshed in the active one.
+///
+/// Inner filters should have room for `capacity` elements. Any `Filter` can be rotated: Bloom
+/// filters, as in the A² paper, or QHTs.
+///
+/// # Example
+/// ```rust
+/// use qht::{BloomFilter, DoubleBufferFilter, Filter, QuotientHashTable};
+/// let mut f = DoubleBufferFilter::new(2, || BloomFilter::new(1024, 3));
+/// assert!( !f.insert(1u64) );
+/// assert!( !f.insert(2u64) ); // The filters rotate
+/// assert!( f.insert(1u64) ); // 1 is still in the previous filter, and refreshed
+/// assert!( !f.insert(3u64) ); // The filters rotate again
+/// assert!( f.lookup(1u64) );
+/// assert!( !f.lookup(2u64) ); // 2 is forgotten
+///
+/// let mut g = DoubleBufferFilter::new(100, || QuotientHashTable::new(1024, 4, 8));
+/// assert!( !g.insert(1234u64) );
+/// ```
+pub struct DoubleBufferFilter<F, N> {
+    /// Filter receiving insertions
+    active: F,
+
+    /// Filter rotated out most recently
+    previous: F,
+
+    /// Builds empty filters
+    new_filter: N,
+
+    /// Number of elements stored in the active filter before a rotation
+    capacity: usize,
+
+    /// Number of elements stored in the active filter
+    stored: usize,
+}
+
+impl<F, N: FnMut() -> F> DoubleBufferFilter<F, N> {
+    /// Returns a double-buffer filter rotating every `capacity` stored elements, or panics if
+    /// `capacity` is zero
+    pub fn new(capacity: usize, mut new_filter: N) -> Self {
+        if capacity == 0 {
+            panic!("[Double-buffer Filter] Incorrect parameters, capacity cannot be zero.");
+        }
+        Self {
+            active: new_filter(),
+            previous: new_filter(),
+            new_filter,
+            capacity,
+            stored: 0,
+        }
+    }
+
+    /// Returns the number of elements stored in the active filter before a rotation
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the filter receiving insertions
+    pub fn active(&self) -> &F {
+        &self.active
+    }
+
+    /// Returns the filter rotated out most recently
+    pub fn previous(&self) -> &F {
+        &self.previous
+    }
+
+    /// Drops the previous filter, makes the active one previous, and activates a fresh one
+    ///
+    /// This happens every `capacity` stored elements, and may be called on other events
+    /// (e.g. periodically, for a time-based horizon).
+    pub fn rotate(&mut self) {
+        let fresh = (self.new_filter)();
+        self.previous = mem::replace(&mut self.active, fresh);
+        self.stored = 0;
+    }
+
+    /// Inserts an element in the active filter with `insert`, and looks it up in the previous one
+    /// with `lookup` if it was not in the active one
+    fn insert_with(
+        &mut self,
+        insert: impl FnOnce(&mut F) -> bool,
+        lookup: impl FnOnce(&F) -> bool,
+    ) -> bool {
+        if insert(&mut self.active) {
+            return true;
+        }
+
+        let present = lookup(&self.previous);
+        self.stored += 1;
+        if self.stored == self.capacity {
+            self.rotate();
+        }
+        present
+    }
+}
+
+impl<F: Filter, N: FnMut() -> F> Filter for DoubleBufferFilter<F, N> {
+    /// Performs a lookup for the provided element
+    fn lookup(&self, e: impl Hash) -> bool {
+        self.active.lookup(&e) || self.previous.lookup(&e)
+    }
+
+    /// Inserts an element, returning whether it was previously present
+    fn insert(&mut self, e: impl Hash) -> bool {
+        self.insert_with(|f| f.insert(&e), |f| f.lookup(&e))
+    }
+}
+
+impl<F: DynFilter, N: FnMut() -> F> DynFilter for DoubleBufferFilter<F, N> {
+    fn lookup_hash(&self, hash: u64) -> bool {
+        self.active.lookup_hash(hash) || self.previous.lookup_hash(hash)
+    }
+
+    fn insert_hash(&mut self, hash: u64) -> bool {
+        self.insert_with(|f| f.insert_hash(hash), |f| f.lookup_hash(hash))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::DoubleBufferFilter;
+    use crate::testing::last_error_rates;
+    use crate::{streams, BloomFilter, QuotientHashTable};
+
+    #[test]
+    fn double_buffer_forgets_beyond_horizon() {
+        // Elements are remembered for 2000 to 4000 distinct insertions
+        let capacity = 2000;
+        let mut rates = Vec::new();
+        for &delay in &[1000, 10_000] {
+            let stream: Vec<u64> = streams::DelayedDuplicates::new(delay, 0.5, 31)
+                .take(50_000)
+                .collect();
+            let bloom = DoubleBufferFilter::new(capacity, || BloomFilter::new(20_000, 7));
+            let qht = DoubleBufferFilter::new(capacity, || QuotientHashTable::new(32_000, 4, 8));
+            rates.push((
+                last_error_rates(bloom, &stream),
+                last_error_rates(qht, &stream),
+            ));
+        }
+
+        for &(bloom, qht) in &rates {
+            assert!(bloom.0 < 0.05);
+            assert!(qht.0 < 0.05);
+        }
+        assert!((rates[0].0).1 < 0.05);
+        assert!((rates[0].1).1 < 0.05);
+        assert!((rates[1].0).1 > 0.9);
+        assert!((rates[1].1).1 > 0.9);
+    }
+}
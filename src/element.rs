@@ -1,12 +1,107 @@
-pub use std::collections::hash_map::DefaultHasher;
-pub use std::hash::{Hash, Hasher};
+use core::hash::{Hash, Hasher};
 
 // --------------------------------------------------------------------------------
 // Elements
 
 /// This struct defines which elements are processed as stream elements
-#[derive(Clone, Copy, Hash)]
+///
+/// Elements hash as their value, hence as the `u64` itself. Other keys (strings, byte vectors,
+/// tuples...) and event metadata are carried by `Record`.
+///
+/// # Example
+/// ```rust
+/// use qht::{Element, Filter, QuotientHashTable};
+/// let mut f = QuotientHashTable::new(1024, 4, 3);
+/// f.insert(Element { value: 1234 });
+/// assert!( f.lookup(Element::from(1234u32)) );
+/// assert!( f.lookup(1234u64) );
+/// ```
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
 pub struct Element {
     /// Value held by the element
     pub value: u64,
 }
+
+macro_rules! impl_element_from {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Element {
+                fn from(value: $t) -> Self {
+                    Element {
+                        value: u64::from(value),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_element_from!(u8, u16, u32, u64);
+
+// --------------------------------------------------------------------------------
+// Records
+
+/// A stream element along with its metadata
+///
+/// The key is generic (`u64` by default), so that strings, byte vectors or tuples can be used as
+/// composite keys. Only the key identifies the record: the timestamp and source id are ignored by
+/// filters, so that the same event reported at different times or by different sources is a duplicate.
+///
+/// # Example
+/// ```rust
+/// use qht::{Element, Filter, QuotientHashTable, Record};
+/// let mut f = QuotientHashTable::new(1024, 4, 3);
+/// let record = Record::new(("GET", "/index.html")).with_timestamp(1_546_300_800).with_source(3);
+/// assert!( !f.insert(record) );
+/// assert!( f.insert(Record::new(("GET", "/index.html")).with_source(4)) );
+///
+/// f.insert(Record::new(1234u64).with_timestamp(1_546_300_860));
+/// assert!( f.lookup(Element { value: 1234 }) ); // Records hash as their key
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Record<K = u64> {
+    /// Key identifying the record
+    pub key: K,
+
+    /// Time of the event, in a unit chosen by the caller (e.g. seconds since the Unix epoch)
+    pub timestamp: Option<u64>,
+
+    /// Identifier of the producer of the event
+    pub source: Option<u32>,
+}
+
+impl<K> Record<K> {
+    /// Returns a record with the provided key, without timestamp nor source id
+    pub fn new(key: K) -> Self {
+        Record {
+            key,
+            timestamp: None,
+            source: None,
+        }
+    }
+
+    /// Sets the timestamp of the record
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Sets the source id of the record
+    pub fn with_source(mut self, source: u32) -> Self {
+        self.source = Some(source);
+        self
+    }
+}
+
+impl<K: Hash> Hash for Record<K> {
+    /// Hashes the key only
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
+}
+
+impl<K> From<K> for Record<K> {
+    fn from(key: K) -> Self {
+        Record::new(key)
+    }
+}
@@ -1,4 +1,8 @@
-pub use std::hash::Hash;
+use crate::basicqht::hash_of;
+use crate::hash::{key_hash, DefaultBuildHasher};
+
+use alloc::boxed::Box;
+use core::hash::Hash;
 // --------------------------------------------------------------------------------
 // Filter
 
@@ -10,3 +14,60 @@ pub trait Filter {
     /// Performs a lookup for the provided element and inserts it
     fn insert(&mut self, e: impl Hash) -> bool;
 }
+
+/// An object-safe companion of `Filter`, taking elements as 64-bit hashes or byte-string keys
+///
+/// Every filter of the crate implements it, so that filters chosen at runtime can be stored as
+/// `Box<dyn DynFilter>` (see `FilterConfig`). Keys are hashed with `hash::key_hash`, as by
+/// `Qht::insert_key`.
+///
+/// # Example
+/// ```rust
+/// use qht::{BloomFilter, DynFilter, QuotientHashTable};
+/// let mut filters: Vec<Box<dyn DynFilter>> = vec![
+///     Box::new(QuotientHashTable::new(1024, 4, 8)),
+///     Box::new(BloomFilter::new(1024, 3)),
+/// ];
+/// for f in filters.iter_mut() {
+///     assert!( !f.insert_key(b"record") );
+///     assert!( f.lookup_key(b"record") );
+/// }
+/// ```
+pub trait DynFilter {
+    /// Performs a lookup for the element of hash `hash`
+    fn lookup_hash(&self, hash: u64) -> bool;
+
+    /// Performs a lookup for the element of hash `hash` and inserts it
+    fn insert_hash(&mut self, hash: u64) -> bool;
+
+    /// Performs a lookup for a byte-string key
+    fn lookup_key(&self, key: &[u8]) -> bool {
+        self.lookup_hash(key_hash(key))
+    }
+
+    /// Performs a lookup for a byte-string key and inserts it
+    fn insert_key(&mut self, key: &[u8]) -> bool {
+        self.insert_hash(key_hash(key))
+    }
+}
+
+impl<F: DynFilter + ?Sized> DynFilter for Box<F> {
+    fn lookup_hash(&self, hash: u64) -> bool {
+        (**self).lookup_hash(hash)
+    }
+
+    fn insert_hash(&mut self, hash: u64) -> bool {
+        (**self).insert_hash(hash)
+    }
+}
+
+/// Elements are hashed with the default hasher, as by filters built with `new`
+impl Filter for Box<dyn DynFilter> {
+    fn lookup(&self, e: impl Hash) -> bool {
+        self.lookup_hash(hash_of(&DefaultBuildHasher::default(), e))
+    }
+
+    fn insert(&mut self, e: impl Hash) -> bool {
+        self.insert_hash(hash_of(&DefaultBuildHasher::default(), e))
+    }
+}
@@ -0,0 +1,536 @@
+use crate::hash::FnvBuildHasher;
+use crate::policy::InsertPolicy;
+use crate::storage::CellStorage;
+#[cfg(feature = "std")]
+use crate::storage::OwnedCellStorage;
+use crate::table::Qht;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::TypeId;
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::BuildHasher;
+
+// --------------------------------------------------------------------------------
+// Serialized format
+//
+// A serialized filter is a `HEADER_LEN` bytes header followed by the buckets, packed
+// back to back (`fingerprint_size` bits each) in little-endian 64-bit words.
+
+/// Magic bytes opening every serialized filter
+pub const MAGIC: [u8; 4] = *b"QHTF";
+
+/// Current version of the format
+///
+/// Version 2 derives addresses and fingerprints from a single hash (see `Qht`), so that filters
+/// serialized by version 1 cannot be read anymore.
+pub const FORMAT_VERSION: u16 = 2;
+
+/// Length of the header, in bytes
+pub const HEADER_LEN: usize = 32;
+
+/// QHT flavour (insertion policy) a serialized filter was created with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flavour {
+    /// `QuotientHashTable`
+    Qht = 0,
+    /// `QQuotientHashTable`
+    QQht = 1,
+    /// `QQuotientHashTableD`
+    QQhtD = 2,
+    /// `LruQuotientHashTable`
+    Lru = 3,
+    /// `ClockQuotientHashTable`
+    Clock = 4,
+    /// Any other policy, which has to be provided again to read the filter
+    Custom = 255,
+}
+
+impl Flavour {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Flavour::Qht),
+            1 => Some(Flavour::QQht),
+            2 => Some(Flavour::QQhtD),
+            3 => Some(Flavour::Lru),
+            4 => Some(Flavour::Clock),
+            255 => Some(Flavour::Custom),
+            _ => None,
+        }
+    }
+}
+
+/// Hash function a serialized filter was populated with
+///
+/// Only fully specified hash functions are recorded, so that a filter reads the same across
+/// Rust releases.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HasherId {
+    /// `hash::FnvHasher`
+    Fnv1a = 0,
+    /// Any other hasher, which has to be provided again to read the filter
+    Custom = 255,
+}
+
+impl HasherId {
+    /// Returns the identifier of the `BuildHasher` `H`
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::format::HasherId;
+    /// use qht::hash::FnvBuildHasher;
+    /// assert_eq!( HasherId::of::<FnvBuildHasher>(), HasherId::Fnv1a );
+    /// ```
+    pub fn of<H: 'static>() -> Self {
+        let id = TypeId::of::<H>();
+        if id == TypeId::of::<FnvBuildHasher>() {
+            HasherId::Fnv1a
+        } else {
+            HasherId::Custom
+        }
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(HasherId::Fnv1a),
+            255 => Some(HasherId::Custom),
+            _ => None,
+        }
+    }
+}
+
+/// Error raised when a buffer does not hold a valid serialized filter
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FormatError {
+    /// The buffer is shorter than the header
+    TooShort,
+    /// The buffer does not start with `MAGIC`
+    BadMagic,
+    /// The format version is not supported
+    UnsupportedVersion(u16),
+    /// The flavour is unknown, or not the expected one
+    BadFlavour(u8),
+    /// The hasher is unknown
+    BadHasher(u8),
+    /// The parameters cannot describe a QHT
+    BadParameters,
+    /// The buffer length does not match the parameters
+    BadLength {
+        /// Length deduced from the header, in bytes
+        expected: usize,
+        /// Actual length, in bytes
+        actual: usize,
+    },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::TooShort => write!(f, "buffer is shorter than a header"),
+            FormatError::BadMagic => write!(f, "not a serialized QHT"),
+            FormatError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            FormatError::BadFlavour(v) => write!(f, "unexpected flavour {}", v),
+            FormatError::BadHasher(v) => write!(f, "unknown hasher {}", v),
+            FormatError::BadParameters => write!(f, "invalid filter parameters"),
+            FormatError::BadLength { expected, actual } => {
+                write!(f, "expected {} bytes, found {}", expected, actual)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FormatError {}
+
+/// Parameters of a serialized filter
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// Flavour of the filter
+    pub flavour: Flavour,
+
+    /// Hash function used to populate the filter
+    pub hasher: HasherId,
+
+    /// Number of cells
+    pub n_cells: usize,
+
+    /// Number of buckets
+    pub n_buckets: usize,
+
+    /// Size of the fingerprint (in bits)
+    pub fingerprint_size: usize,
+
+    /// Number of candidate cells per element (see `Qht::with_choices`)
+    pub choices: usize,
+}
+
+impl Header {
+    /// Returns the length of the packed buckets, in bytes
+    ///
+    /// Fails with `BadParameters` if it overflows a `usize`.
+    pub fn payload_len(&self) -> Result<usize, FormatError> {
+        self.n_cells
+            .checked_mul(self.n_buckets)
+            .and_then(|n| n.checked_mul(self.fingerprint_size))
+            .and_then(|bits| (bits / 64 + (bits % 64 != 0) as usize).checked_mul(8))
+            .ok_or(FormatError::BadParameters)
+    }
+
+    /// Returns the length of the whole serialized filter, in bytes
+    ///
+    /// Fails with `BadParameters` if it overflows a `usize`.
+    pub fn total_len(&self) -> Result<usize, FormatError> {
+        self.payload_len()?
+            .checked_add(HEADER_LEN)
+            .ok_or(FormatError::BadParameters)
+    }
+
+    /// Encodes the header
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::format::{Flavour, Header, HasherId};
+    /// let header = Header {
+    ///     flavour: Flavour::Qht,
+    ///     hasher: HasherId::Fnv1a,
+    ///     n_cells: 341,
+    ///     n_buckets: 1,
+    ///     fingerprint_size: 3,
+    ///     choices: 1,
+    /// };
+    /// assert_eq!( Header::decode(&header.encode()), Ok(header) );
+    /// ```
+    pub fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+        bytes[0..4].copy_from_slice(&MAGIC);
+        bytes[4..6].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes[6] = self.flavour as u8;
+        bytes[7] = self.hasher as u8;
+        bytes[8..16].copy_from_slice(&(self.n_cells as u64).to_le_bytes());
+        bytes[16..24].copy_from_slice(&(self.n_buckets as u64).to_le_bytes());
+        bytes[24] = self.fingerprint_size as u8;
+        bytes[25] = self.choices as u8;
+        bytes
+    }
+
+    /// Decodes and validates a header from the beginning of `bytes`
+    pub fn decode(bytes: &[u8]) -> Result<Self, FormatError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(FormatError::TooShort);
+        }
+        if bytes[0..4] != MAGIC {
+            return Err(FormatError::BadMagic);
+        }
+
+        let version = read_u16(&bytes[4..6]);
+        if version != FORMAT_VERSION {
+            return Err(FormatError::UnsupportedVersion(version));
+        }
+
+        let flavour = Flavour::from_u8(bytes[6]).ok_or(FormatError::BadFlavour(bytes[6]))?;
+        let hasher = HasherId::from_u8(bytes[7]).ok_or(FormatError::BadHasher(bytes[7]))?;
+        let n_cells = read_usize(&bytes[8..16])?;
+        let n_buckets = read_usize(&bytes[16..24])?;
+        let fingerprint_size = bytes[24] as usize;
+        let choices = bytes[25] as usize;
+
+        if n_cells == 0
+            || n_buckets == 0
+            || fingerprint_size == 0
+            || fingerprint_size > crate::basicqht::FINGERPRINT_SIZE_LIMIT
+            || choices == 0
+        {
+            return Err(FormatError::BadParameters);
+        }
+
+        let header = Self {
+            flavour,
+            hasher,
+            n_cells,
+            n_buckets,
+            fingerprint_size,
+            choices,
+        };
+        header.total_len()?;
+        Ok(header)
+    }
+
+    /// Decodes a header and checks that `bytes` holds exactly the whole filter
+    pub fn decode_exact(bytes: &[u8]) -> Result<Self, FormatError> {
+        let header = Self::decode(bytes)?;
+        let expected = header.total_len()?;
+        if bytes.len() != expected {
+            return Err(FormatError::BadLength {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+        Ok(header)
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Packed little-endian bit access
+
+/// Reads `width` bits starting at bit `offset` of little-endian packed `words`
+pub(crate) fn read_bits(words: &[u8], offset: usize, width: usize) -> u64 {
+    let mut value = 0;
+    let mut done = 0;
+    while done < width {
+        let bit = offset + done;
+        let shift = bit % 64;
+        let count = core::cmp::min(64 - shift, width - done);
+        let word = read_u64(&words[bit / 64 * 8..bit / 64 * 8 + 8]);
+        value |= ((word >> shift) & mask(count)) << done;
+        done += count;
+    }
+    value
+}
+
+/// Writes the `width` lowest bits of `value` starting at bit `offset` of little-endian packed `words`
+pub(crate) fn write_bits(words: &mut [u8], offset: usize, width: usize, value: u64) {
+    let mut done = 0;
+    while done < width {
+        let bit = offset + done;
+        let shift = bit % 64;
+        let count = core::cmp::min(64 - shift, width - done);
+        let bytes = &mut words[bit / 64 * 8..bit / 64 * 8 + 8];
+        let m = mask(count) << shift;
+        let word = (read_u64(bytes) & !m) | (((value >> done) << shift) & m);
+        bytes.copy_from_slice(&word.to_le_bytes());
+        done += count;
+    }
+}
+
+fn mask(width: usize) -> u64 {
+    if width >= 64 {
+        !0
+    } else {
+        (1 << width) - 1
+    }
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    let mut buffer = [0; 2];
+    buffer.copy_from_slice(bytes);
+    u16::from_le_bytes(buffer)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut buffer = [0; 8];
+    buffer.copy_from_slice(bytes);
+    u64::from_le_bytes(buffer)
+}
+
+/// Reads a count, failing with `BadParameters` if it does not fit in a `usize`
+fn read_usize(bytes: &[u8]) -> Result<usize, FormatError> {
+    usize::try_from(read_u64(bytes)).map_err(|_| FormatError::BadParameters)
+}
+
+// --------------------------------------------------------------------------------
+// Serialization of tables
+
+impl<P: InsertPolicy, S: CellStorage, H: BuildHasher + 'static> Qht<P, S, H> {
+    /// Returns the parameters describing the filter
+    pub fn header(&self) -> Header {
+        Header {
+            flavour: P::FLAVOUR,
+            hasher: HasherId::of::<H>(),
+            n_cells: self.n_cells,
+            n_buckets: self.n_buckets,
+            fingerprint_size: self.fingerprint_size,
+            choices: self.choices,
+        }
+    }
+
+    /// Serializes the filter in the crate's format (see `format`)
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::{Element, Filter, QuotientHashTable};
+    /// let mut f = QuotientHashTable::new(1024, 4, 3);
+    /// f.insert(Element { value: 1234 });
+    ///
+    /// let g = QuotientHashTable::from_bytes(&f.to_bytes()).unwrap();
+    /// assert!( g.lookup(Element { value: 1234 }) );
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let header = self.header();
+        // The buckets fit in memory, hence in a buffer
+        let len = header.total_len().unwrap();
+        let mut bytes = Vec::with_capacity(len);
+        bytes.extend_from_slice(&header.encode());
+        bytes.resize(len, 0);
+
+        let payload = &mut bytes[HEADER_LEN..];
+        let mut cell = vec![0; self.n_buckets];
+        for address in 0..self.n_cells {
+            let first = address * self.n_buckets;
+            self.qht.load_cell(first, self.fingerprint_size, &mut cell);
+            for (idx, &fingerprint) in cell.iter().enumerate() {
+                write_bits(
+                    payload,
+                    (first + idx) * self.fingerprint_size,
+                    self.fingerprint_size,
+                    fingerprint,
+                );
+            }
+        }
+        bytes
+    }
+
+    /// Replaces the content of the filter with the serialized filter held in `bytes`
+    ///
+    /// Fails if `bytes` does not hold exactly a filter of this flavour, with the same
+    /// parameters and hasher.
+    pub fn load_bytes(&mut self, bytes: &[u8]) -> Result<(), FormatError> {
+        let header = Header::decode_exact(bytes)?;
+        let expected = self.header();
+        if header.flavour != expected.flavour {
+            return Err(FormatError::BadFlavour(header.flavour as u8));
+        }
+        if header.hasher != expected.hasher {
+            return Err(FormatError::BadHasher(header.hasher as u8));
+        }
+        if header != expected {
+            return Err(FormatError::BadParameters);
+        }
+
+        // The policy's own state is not serialized
+        self.policy.init(self.n_cells, self.n_buckets);
+
+        let width = self.fingerprint_size;
+        let payload = &bytes[HEADER_LEN..];
+        for idx in 0..self.n_cells * self.n_buckets {
+            self.qht
+                .set_bucket(idx, width, read_bits(payload, idx * width, width));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P: InsertPolicy + Default, S: OwnedCellStorage> Qht<P, S> {
+    /// Deserializes a filter using `S` as its underlying data structure
+    ///
+    /// Fails if `bytes` does not hold exactly a filter of this flavour
+    pub fn from_bytes_in(bytes: &[u8]) -> Result<Self, FormatError> {
+        // Checked before allocating the storage, since the header cannot be trusted
+        let header = Header::decode_exact(bytes)?;
+
+        let qht = S::with_buckets(header.n_cells * header.n_buckets, header.fingerprint_size);
+        let mut filter = Self::with_storage(qht, header.n_buckets, header.fingerprint_size);
+        filter.set_n_cells(header.n_cells);
+        filter.choices = header.choices;
+        filter.load_bytes(bytes)?;
+        Ok(filter)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P: InsertPolicy + Default> Qht<P> {
+    /// Deserializes a filter
+    ///
+    /// Fails if `bytes` does not hold exactly a filter of this flavour
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FormatError> {
+        Self::from_bytes_in(bytes)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{
+        Filter, LruQuotientHashTable, QQuotientHashTable, QQuotientHashTableD, QhtView,
+        QuotientHashTable,
+    };
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Checks that serialized filters and views answer like the original filter
+    fn check_serialization<F: Filter>(
+        mut f: F,
+        to_bytes: impl Fn(&F) -> Vec<u8>,
+        from_bytes: impl Fn(&[u8]) -> Result<F, FormatError>,
+    ) {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..1000 {
+            f.insert(rng.gen_range(0, 3000u64));
+        }
+
+        let bytes = to_bytes(&f);
+        let g = from_bytes(&bytes).unwrap();
+        let view = QhtView::new(&bytes).unwrap();
+        assert_eq!(to_bytes(&g), bytes);
+        for value in 0..3000u64 {
+            assert_eq!(g.lookup(value), f.lookup(value));
+            assert_eq!(view.lookup(value), f.lookup(value));
+        }
+
+        assert!(from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(QhtView::new(&bytes[..bytes.len() - 1]).is_err());
+        let mut corrupted = bytes.clone();
+        corrupted[0] ^= 1;
+        assert_eq!(QhtView::new(&corrupted).err(), Some(FormatError::BadMagic));
+    }
+
+    #[test]
+    fn serialization_roundtrips() {
+        check_serialization(
+            QuotientHashTable::new(2000, 3, 5),
+            QuotientHashTable::to_bytes,
+            QuotientHashTable::from_bytes,
+        );
+        check_serialization(
+            QQuotientHashTable::<Vec<u8>>::new_in(2000, 3, 5),
+            QQuotientHashTable::to_bytes,
+            QQuotientHashTable::from_bytes_in,
+        );
+        // Views compute addresses with a multiply-shift, which must agree with the shift
+        check_serialization(
+            LruQuotientHashTable::new(5000, 3, 5).with_power_of_two_cells(),
+            LruQuotientHashTable::to_bytes,
+            LruQuotientHashTable::from_bytes,
+        );
+        check_serialization(
+            QQuotientHashTableD::<Vec<u64>>::new_in(2000, 20, 7),
+            QQuotientHashTableD::to_bytes,
+            QQuotientHashTableD::from_bytes_in,
+        );
+
+        let bytes = QuotientHashTable::new(2000, 3, 5).to_bytes();
+        assert!(QQuotientHashTable::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn untrusted_headers() {
+        let mut header = Header {
+            flavour: Flavour::QQhtD,
+            hasher: HasherId::Fnv1a,
+            n_cells: 1000,
+            n_buckets: 5_000_000_000,
+            fingerprint_size: 8,
+            choices: 1,
+        };
+        assert_eq!(Header::decode(&header.encode()), Ok(header));
+
+        // Sizes are checked before they are used
+        header.n_cells = 1 << 61;
+        header.n_buckets = 1;
+        assert_eq!(
+            Header::decode(&header.encode()),
+            Err(FormatError::BadParameters)
+        );
+        header.n_cells = 1 << 40;
+        assert_eq!(
+            Header::decode_exact(&header.encode()),
+            Err(FormatError::BadLength {
+                expected: HEADER_LEN + (1 << 40),
+                actual: HEADER_LEN,
+            })
+        );
+        assert!(QhtView::new(&header.encode()).is_err());
+        assert!(QuotientHashTable::from_bytes(&header.encode()).is_err());
+    }
+}
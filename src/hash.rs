@@ -0,0 +1,121 @@
+use core::hash::{BuildHasherDefault, Hasher};
+
+// --------------------------------------------------------------------------------
+// Hashers
+
+/// 64-bit FNV-1a hasher
+///
+/// A dependency-free `Hasher`, fully specified so that hashes do not change across Rust releases.
+///
+/// # Example
+/// ```rust
+/// use qht::hash::FnvHasher;
+/// use std::hash::Hasher;
+/// let mut h = FnvHasher::default();
+/// h.write(b"a");
+/// assert_eq!( h.finish(), 0xaf63dc4c8601ec8c );
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct FnvHasher(u64);
+
+/// FNV-1a 64-bit offset basis
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// FNV-1a 64-bit prime
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(FNV_OFFSET_BASIS)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Builds `FnvHasher`s
+pub type FnvBuildHasher = BuildHasherDefault<FnvHasher>;
+
+/// Hasher used by filters unless another one is provided
+///
+/// This is `FnvHasher` with or without the `std` feature: unlike `std`'s `DefaultHasher`, whose
+/// algorithm may change between Rust releases, it lets persisted filters be read by other builds.
+pub type DefaultBuildHasher = FnvBuildHasher;
+
+// --------------------------------------------------------------------------------
+// Language-independent key hashing
+
+/// Returns the 64-bit hash of a byte-string key, as used by `Qht::insert_key` and `Qht::lookup_key`
+///
+/// Unlike `Hash`-based hashing, this function is fully specified, so that other implementations
+/// can compute the same addresses and fingerprints:
+///
+/// 1. `h` is the 64-bit FNV-1a hash of the key (offset basis `0xcbf29ce484222325`, prime `0x100000001b3`);
+/// 2. `h` is mixed with the SplitMix64 finalizer `mix`:
+///    `z ^= z >> 30; z *= 0xbf58476d1ce4e5b9; z ^= z >> 27; z *= 0x94d049bb133111eb; z ^= z >> 31`
+///    (wrapping multiplications).
+///
+/// The element then lives in the cell of index `(h * n_cells) >> 64` (as a 128-bit product), and
+/// its fingerprint is `h mod 2^fingerprint_size`. Should the fingerprint be zero, it is taken from
+/// `mix(h + k * 0x9e3779b97f4a7c15) mod 2^fingerprint_size` for `k = 1, 2, ...` instead. With several
+/// choices, the `c`-th candidate cell (`c >= 1`) is addressed with `mix(h + c * 0x9e3779b97f4a7c15)`.
+///
+/// Strings are hashed as their UTF-8 bytes. Conformance vectors can be found in `vectors/key_hash.csv`,
+/// and a reference implementation in `vectors/key_hash.py`.
+///
+/// # Example
+/// ```rust
+/// use qht::hash::key_hash;
+/// assert_eq!( key_hash(b""), 0xf52a_15e9_a9b5_e89b );
+/// assert_eq!( key_hash("foobar"), key_hash(b"foobar") );
+/// ```
+pub fn key_hash(key: impl AsRef<[u8]>) -> u64 {
+    let mut hasher = FnvHasher::default();
+    hasher.write(key.as_ref());
+    crate::basicqht::mix(hasher.finish())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::key_hash;
+    use crate::basicqht::{address_of, candidate_hash, fingerprint_of, BasicQHT};
+    use crate::QuotientHashTable;
+
+    #[test]
+    fn key_hash_conformance() {
+        let vectors = include_str!("../vectors/key_hash.csv");
+        for line in vectors.lines().skip(1) {
+            let fields: Vec<&str> = line.split(',').collect();
+            let key: Vec<u8> = (0..fields[0].len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&fields[0][i..i + 2], 16).unwrap())
+                .collect();
+            let hash = u64::from_str_radix(fields[1], 16).unwrap();
+            let n_cells: usize = fields[2].parse().unwrap();
+            let fingerprint_size: usize = fields[3].parse().unwrap();
+            let address: usize = fields[4].parse().unwrap();
+            let second_address: usize = fields[5].parse().unwrap();
+            let fingerprint: u64 = fields[6].parse().unwrap();
+
+            assert_eq!(key_hash(&key), hash);
+            assert_eq!(address_of(hash, n_cells), address);
+            assert_eq!(address_of(candidate_hash(hash, 1), n_cells), second_address);
+            assert_eq!(fingerprint_of(hash, 1 << fingerprint_size), fingerprint);
+
+            // Tables agree, including with a power of two cells
+            let mut f = QuotientHashTable::new(n_cells * fingerprint_size, 1, fingerprint_size);
+            f.insert_key(&key);
+            assert_eq!(f.get_fingerprint_from_bucket(address, 0), fingerprint);
+        }
+    }
+}
@@ -1,18 +1,64 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+#[cfg(feature = "mmap")]
+extern crate memmap;
 extern crate rand;
+#[cfg(feature = "std")]
 extern crate rust_dense_bitset;
 
 mod filter;
 
-#[macro_use]
+#[cfg(feature = "std")]
+mod accuracy;
 mod basicqht;
+mod bloom;
+mod clock;
+#[cfg(feature = "std")]
+mod config;
+mod counting;
+mod cuckoo;
+mod double_buffer;
 mod element;
+pub mod format;
+pub mod hash;
+mod lru;
+mod map;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+mod policy;
 mod qht;
 mod qqht;
 mod qqhtd;
+pub mod simd;
+pub mod storage;
+#[cfg(feature = "std")]
+pub mod streams;
+mod table;
+#[cfg(all(test, feature = "std"))]
+mod testing;
+mod view;
 
-pub use crate::basicqht::BasicQHT;
-pub use crate::element::Element;
-pub use crate::filter::Filter;
-pub use crate::qht::QuotientHashTable;
-pub use crate::qqht::QQuotientHashTable;
-pub use crate::qqhtd::QQuotientHashTableD;
\ No newline at end of file
+#[cfg(feature = "std")]
+pub use crate::accuracy::{AccuracyTracker, ConfusionMatrix};
+pub use crate::basicqht::{BasicQHT, FINGERPRINT_SIZE_LIMIT};
+pub use crate::bloom::{BloomFilter, StableBloomFilter, STABLE_CELL_SIZE_LIMIT};
+pub use crate::clock::{Clock, ClockQuotientHashTable};
+#[cfg(feature = "std")]
+pub use crate::config::FilterConfig;
+pub use crate::counting::{CountingQuotientHashTable, COUNTER_SIZE_LIMIT};
+pub use crate::cuckoo::{CuckooFilter, DEFAULT_MAX_KICKS};
+pub use crate::double_buffer::DoubleBufferFilter;
+pub use crate::element::{Element, Record};
+pub use crate::filter::{DynFilter, Filter};
+pub use crate::lru::{LruQuotientHashTable, LruRefresh};
+pub use crate::map::{MapValue, QhtMap, VALUE_SIZE_LIMIT};
+#[cfg(feature = "mmap")]
+pub use crate::mmap::MmapStorage;
+pub use crate::policy::InsertPolicy;
+pub use crate::qht::{QuotientHashTable, RandomEviction};
+pub use crate::qqht::{QQuotientHashTable, QueuedRandomEviction};
+pub use crate::qqhtd::{FifoShift, QQuotientHashTableD};
+pub use crate::storage::CellStorage;
+pub use crate::table::{Cell, Qht};
+pub use crate::view::QhtView;
@@ -0,0 +1,53 @@
+use crate::basicqht::Fingerprint;
+use crate::format::Flavour;
+use crate::hash::DefaultBuildHasher;
+use crate::policy::InsertPolicy;
+use crate::storage::{CellStorage, DefaultStorage};
+use crate::table::{Cell, Qht};
+
+// --------------------------------------------------------------------------------
+
+/// Least Recently Used Quotient Hash Table ("compact")
+///
+/// Fingerprints are kept in order of last insertion within each cell, so that elements that
+/// keep being seen are never evicted, and a miss evicts the least recently seen fingerprint.
+///
+/// # Example
+/// ```rust
+/// use qht::{Element, Filter, LruQuotientHashTable};
+/// let mut f = LruQuotientHashTable::new(1024, 2, 3);
+/// let e = Element { value: 1234 };
+/// assert!( !f.insert(e) ); // The filter did not previously contain e
+/// assert!( f.insert(e) ); // Refreshes e, without duplicating it
+/// assert!( f.lookup(e) );
+/// ```
+pub type LruQuotientHashTable<S = DefaultStorage, H = DefaultBuildHasher> = Qht<LruRefresh, S, H>;
+
+/// Insertion policy of `LruQuotientHashTable`
+///
+/// Buckets are ordered from least to most recently seen. On a hit, the fingerprint is moved to the
+/// last bucket; on a miss, the fingerprints are shifted by one position, evicting the first one.
+/// Empty buckets therefore always come first.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LruRefresh;
+
+impl InsertPolicy for LruRefresh {
+    const FLAVOUR: Flavour = Flavour::Lru;
+
+    fn insert<S: CellStorage>(
+        &mut self,
+        cell: &mut Cell<S>,
+        fingerprint: Fingerprint,
+        position: Option<usize>,
+    ) -> Option<usize> {
+        let last_bucket = cell.n_buckets() - 1;
+        match position {
+            Some(bucket) => cell.rotate(bucket, last_bucket),
+            None => {
+                cell.rotate(0, last_bucket);
+                cell.set(last_bucket, fingerprint);
+            }
+        }
+        Some(last_bucket)
+    }
+}
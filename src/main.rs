@@ -1 +1,1060 @@
-fn main() {}
+//! `qht`: approximate, memory-bounded removal of duplicate records
+//!
+//! Run `qht --help` for usage.
+
+extern crate qht;
+extern crate rand;
+
+use qht::format::{Flavour, FormatError, Header};
+use qht::hash::DefaultBuildHasher;
+use qht::streams::{self, Bursty, DelayedDuplicates, Uniform, Zipf};
+use qht::{
+    Clock, ConfusionMatrix, FifoShift, InsertPolicy, LruRefresh, Qht, QhtView,
+    QueuedRandomEviction, RandomEviction, FINGERPRINT_SIZE_LIMIT,
+};
+use rand::rngs::StdRng;
+use rand::{FromEntropy, Rng, SeedableRng};
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process;
+use std::time::Instant;
+
+const USAGE: &str = "\
+Usage: qht [dedup] [OPTIONS] [--state SNAPSHOT] [FILE]...
+       qht evaluate [OPTIONS] [--input] FILE...
+       qht inspect SNAPSHOT
+       qht sweep [OPTIONS] [--stream KIND] [--length N] [--universe N]
+
+dedup (default): prints the records (lines) of the FILEs, or of the standard
+input, that are not detected as duplicates. Detection is approximate but uses
+bounded memory. With --state, the filter is loaded from SNAPSHOT if it exists
+(its parameters then override the options), and saved to it afterwards.
+
+evaluate: streams the records of the FILEs through each flavour (or the one
+given by --flavour) and prints the measured false positive and false negative
+rates, throughput and memory, against the exact set of records.
+
+inspect: prints the parameters, occupancy and estimated false positive rate
+of a filter saved with --state.
+
+sweep: generates a synthetic stream, and prints as CSV the false positive and
+false negative rates of qht, qqht and qqhtd (or the --flavour) on it, for each
+combination of --memory, --buckets and --fingerprint-bits, which then accept
+comma-separated lists (default: 64K,256K,1M; 1,2,4,8; 8). The same stream is
+used for all flavours and parameters.
+
+Options:
+  --flavour NAME           qht, qqht, qqhtd, lru or clock (default: qht)
+  --memory SIZE            memory of the filter, in bytes, with an optional
+                           K, M or G suffix (default: 16M)
+  --buckets N              buckets per cell (default: 4)
+  --fingerprint-bits N     size of the fingerprints, from 1 to 8 (default: 8)
+  --seed N                 seed of the random evictions (default: random)
+  --invert                 dedup: print only the records detected as duplicates
+  --state SNAPSHOT         dedup: load and save the filter in SNAPSHOT
+  --stream KIND            sweep: uniform, zipf, bursty (bursts of 1000
+                           elements among 100) or delayed (elements repeated
+                           --delay elements later with probability 1/2)
+                           (default: uniform)
+  --exponent S             sweep: exponent of the zipf stream (default: 1)
+  --delay N                sweep: delay of the delayed stream (default: 10000)
+  --length N               sweep: number of elements (default: 1000000)
+  --universe N             sweep: number of distinct possible elements
+                           (default: 1000000)
+  -z, --zero-terminated    records are delimited by NUL bytes, not newlines
+  -h, --help               print this help
+";
+
+/// Flavours compared by `sweep`, as in the QHT paper
+const SWEPT_FLAVOURS: [Flavour; 3] = [Flavour::Qht, Flavour::QQht, Flavour::QQhtD];
+
+/// Flavours selectable from the command line
+const FLAVOURS: [Flavour; 5] = [
+    Flavour::Qht,
+    Flavour::QQht,
+    Flavour::QQhtD,
+    Flavour::Lru,
+    Flavour::Clock,
+];
+
+/// Streams generated by `sweep`
+const STREAM_KINDS: [StreamKind; 4] = [
+    StreamKind::Uniform,
+    StreamKind::Zipf,
+    StreamKind::Bursty,
+    StreamKind::Delayed,
+];
+
+// --------------------------------------------------------------------------------
+// Options
+
+/// Subcommands
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Command {
+    Dedup,
+    Evaluate,
+    Inspect,
+    Sweep,
+}
+
+/// Distributions of the synthetic streams of `sweep`
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StreamKind {
+    /// Elements drawn uniformly from the universe
+    Uniform,
+
+    /// Elements drawn from the universe following Zipf's law
+    Zipf,
+
+    /// Bursts of elements drawn from a few elements of the universe
+    Bursty,
+
+    /// Fresh elements, repeated after a fixed delay
+    Delayed,
+}
+
+impl StreamKind {
+    fn name(self) -> &'static str {
+        match self {
+            StreamKind::Uniform => "uniform",
+            StreamKind::Zipf => "zipf",
+            StreamKind::Bursty => "bursty",
+            StreamKind::Delayed => "delayed",
+        }
+    }
+}
+
+/// Parameters of `sweep`
+#[derive(Clone)]
+struct Sweep {
+    /// Memories of the filters, in bits
+    memories: Vec<usize>,
+
+    /// Numbers of buckets
+    buckets: Vec<usize>,
+
+    /// Sizes of the fingerprints, in bits
+    fingerprint_bits: Vec<usize>,
+
+    /// Distribution of the stream
+    stream: StreamKind,
+
+    /// Exponent of the Zipf distribution
+    exponent: f64,
+
+    /// Delay of the repetitions of the delayed stream
+    delay: usize,
+
+    /// Number of elements of the stream
+    length: usize,
+
+    /// Number of distinct possible elements
+    universe: usize,
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Sweep {
+            memories: vec![64 << 13, 256 << 13, 1 << 23],
+            buckets: vec![1, 2, 4, 8],
+            fingerprint_bits: vec![8],
+            stream: StreamKind::Uniform,
+            exponent: 1.0,
+            delay: 10_000,
+            length: 1_000_000,
+            universe: 1_000_000,
+        }
+    }
+}
+
+/// Parsed command line
+#[derive(Clone)]
+struct Options {
+    /// Subcommand to run
+    command: Command,
+
+    /// Insertion policy of the filter, if chosen
+    flavour: Option<Flavour>,
+
+    /// Memory of the filter, in bits
+    memory: usize,
+
+    /// Number of buckets
+    buckets: usize,
+
+    /// Size of the fingerprints, in bits
+    fingerprint_bits: usize,
+
+    /// Number of candidate cells per element
+    choices: usize,
+
+    /// Seed of the random number generator
+    seed: Option<u64>,
+
+    /// Whether duplicates are printed instead of unique records
+    invert: bool,
+
+    /// Snapshot the filter is loaded from and saved to
+    state: Option<String>,
+
+    /// Record delimiter
+    delimiter: u8,
+
+    /// Input files, the standard input being used if empty
+    inputs: Vec<String>,
+
+    /// Parameters of `sweep`
+    sweep: Sweep,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            command: Command::Dedup,
+            flavour: None,
+            memory: 16 << 23,
+            buckets: 4,
+            fingerprint_bits: 8,
+            choices: 1,
+            seed: None,
+            invert: false,
+            state: None,
+            delimiter: b'\n',
+            inputs: Vec::new(),
+            sweep: Sweep::default(),
+        }
+    }
+}
+
+/// Parses the command line (without the program name)
+///
+/// Returns `None` if help was requested.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter().peekable();
+
+    match args.peek().map(String::as_str) {
+        Some("dedup") => {
+            args.next();
+        }
+        Some("evaluate") => {
+            options.command = Command::Evaluate;
+            args.next();
+        }
+        Some("inspect") => {
+            options.command = Command::Inspect;
+            args.next();
+        }
+        Some("sweep") => {
+            options.command = Command::Sweep;
+            args.next();
+        }
+        _ => (),
+    }
+
+    while let Some(arg) = args.next() {
+        // Options accept both `--name value` and `--name=value`
+        let (name, inline_value) = match arg.find('=') {
+            Some(idx) if arg.starts_with("--") => {
+                (arg[..idx].to_string(), Some(arg[idx + 1..].to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("missing value for {}", name))
+        };
+
+        let sweep = options.command == Command::Sweep;
+        match name.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--memory" if sweep => {
+                options.sweep.memories = parse_list(&value()?, |s| Ok(parse_size(s)? * 8))?
+            }
+            "--buckets" if sweep => {
+                options.sweep.buckets = parse_list(&value()?, |s| parse_number(&name, s))?
+            }
+            "--fingerprint-bits" if sweep => {
+                options.sweep.fingerprint_bits = parse_list(&value()?, |s| parse_number(&name, s))?
+            }
+            "--stream" if sweep => options.sweep.stream = parse_stream(&value()?)?,
+            "--exponent" if sweep => options.sweep.exponent = parse_number(&name, &value()?)?,
+            "--delay" if sweep => options.sweep.delay = parse_number(&name, &value()?)?,
+            "--length" if sweep => options.sweep.length = parse_number(&name, &value()?)?,
+            "--universe" if sweep => options.sweep.universe = parse_number(&name, &value()?)?,
+            "--flavour" => options.flavour = Some(parse_flavour(&value()?)?),
+            "--memory" => options.memory = parse_size(&value()?)? * 8,
+            "--buckets" => options.buckets = parse_number(&name, &value()?)?,
+            "--fingerprint-bits" => options.fingerprint_bits = parse_number(&name, &value()?)?,
+            "--seed" => options.seed = Some(parse_number(&name, &value()?)?),
+            "--invert" if options.command == Command::Dedup => options.invert = true,
+            "--state" if options.command == Command::Dedup => options.state = Some(value()?),
+            "--input" if options.command == Command::Evaluate => options.inputs.push(value()?),
+            "-z" | "--zero-terminated" => options.delimiter = 0,
+            "-" if !sweep => options.inputs.push(arg),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if !sweep => options.inputs.push(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    if options.buckets == 0 {
+        return Err("--buckets cannot be zero".to_string());
+    }
+    if options.fingerprint_bits == 0 || options.fingerprint_bits > FINGERPRINT_SIZE_LIMIT {
+        return Err(format!(
+            "--fingerprint-bits should be between 1 and {}",
+            FINGERPRINT_SIZE_LIMIT
+        ));
+    }
+    if options.memory < options.buckets * options.fingerprint_bits {
+        return Err("--memory cannot hold a single cell".to_string());
+    }
+    if options.command == Command::Evaluate && options.inputs.is_empty() {
+        return Err("evaluate requires an input file".to_string());
+    }
+    if options.command == Command::Inspect && options.inputs.len() != 1 {
+        return Err("inspect requires a single snapshot".to_string());
+    }
+    if options.command == Command::Sweep {
+        check_sweep(&options.sweep)?;
+    }
+    Ok(Some(options))
+}
+
+fn parse_flavour(s: &str) -> Result<Flavour, String> {
+    FLAVOURS
+        .iter()
+        .cloned()
+        .find(|&flavour| flavour_name(flavour) == s)
+        .ok_or_else(|| format!("unknown flavour {}", s))
+}
+
+fn parse_stream(s: &str) -> Result<StreamKind, String> {
+    STREAM_KINDS
+        .iter()
+        .cloned()
+        .find(|kind| kind.name() == s)
+        .ok_or_else(|| format!("unknown stream {}", s))
+}
+
+fn flavour_name(flavour: Flavour) -> &'static str {
+    match flavour {
+        Flavour::Qht => "qht",
+        Flavour::QQht => "qqht",
+        Flavour::QQhtD => "qqhtd",
+        Flavour::Lru => "lru",
+        Flavour::Clock => "clock",
+        Flavour::Custom => "custom",
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, s: &str) -> Result<T, String> {
+    s.parse()
+        .map_err(|_| format!("invalid value {} for {}", s, name))
+}
+
+/// Parses a non-empty, comma-separated list
+fn parse_list<T>(s: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<Vec<T>, String> {
+    s.split(',').map(|item| parse(item.trim())).collect()
+}
+
+/// Checks that every combination of the parameters of `sweep` describes a filter
+fn check_sweep(sweep: &Sweep) -> Result<(), String> {
+    if sweep.buckets.contains(&0) {
+        return Err("--buckets cannot be zero".to_string());
+    }
+    if sweep
+        .fingerprint_bits
+        .iter()
+        .any(|&bits| bits == 0 || bits > FINGERPRINT_SIZE_LIMIT)
+    {
+        return Err(format!(
+            "--fingerprint-bits should be between 1 and {}",
+            FINGERPRINT_SIZE_LIMIT
+        ));
+    }
+    let largest_cell =
+        sweep.buckets.iter().max().unwrap() * sweep.fingerprint_bits.iter().max().unwrap();
+    if sweep.memories.iter().any(|&memory| memory < largest_cell) {
+        return Err("--memory cannot hold a single cell".to_string());
+    }
+    if sweep.universe == 0 {
+        return Err("--universe cannot be zero".to_string());
+    }
+    if sweep.delay == 0 {
+        return Err("--delay cannot be zero".to_string());
+    }
+    if sweep.exponent.is_nan() || sweep.exponent <= 0.0 {
+        return Err("--exponent should be positive".to_string());
+    }
+    Ok(())
+}
+
+/// Parses a number of bytes, with an optional K, M or G suffix
+fn parse_size(s: &str) -> Result<usize, String> {
+    let (digits, shift) = match s.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&s[..s.len() - 1], 10),
+        Some('M') => (&s[..s.len() - 1], 20),
+        Some('G') => (&s[..s.len() - 1], 30),
+        _ => (s, 0),
+    };
+    let size: usize = parse_number("--memory", digits)?;
+    size.checked_mul(1 << shift)
+        .ok_or_else(|| format!("--memory {} is too large", s))
+}
+
+// --------------------------------------------------------------------------------
+// Tables
+
+/// An operation over a table of any flavour
+trait TableVisitor {
+    type Output;
+
+    fn visit<P: InsertPolicy>(self, filter: Qht<P>) -> Self::Output;
+}
+
+/// Builds the table of the given flavour described by `options`, and hands it to `visitor`
+fn with_table<V: TableVisitor>(flavour: Flavour, options: &Options, visitor: V) -> V::Output {
+    fn table<P: InsertPolicy>(options: &Options, policy: P) -> Qht<P> {
+        Qht::with_hasher(
+            options.memory,
+            options.buckets,
+            options.fingerprint_bits,
+            DefaultBuildHasher::default(),
+            policy,
+        )
+        .with_choices(options.choices)
+    }
+
+    let rng = seeded_rng(options);
+    match flavour {
+        Flavour::Qht => visitor.visit(table(options, RandomEviction::with_rng(rng))),
+        Flavour::QQht => visitor.visit(table(options, QueuedRandomEviction::with_rng(rng))),
+        Flavour::QQhtD => visitor.visit(table(options, FifoShift)),
+        Flavour::Lru => visitor.visit(table(options, LruRefresh)),
+        Flavour::Clock => visitor.visit(table(options, Clock::default())),
+        Flavour::Custom => unreachable!(),
+    }
+}
+
+/// Returns a random number generator seeded with `--seed`, or from the OS
+fn seeded_rng(options: &Options) -> StdRng {
+    match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// Calls `process` on each record of the inputs, without its delimiter
+fn for_each_record(
+    options: &Options,
+    mut process: impl FnMut(&[u8]) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut read = |input: &mut dyn BufRead| -> io::Result<()> {
+        let mut record = Vec::new();
+        loop {
+            record.clear();
+            if input.read_until(options.delimiter, &mut record)? == 0 {
+                return Ok(());
+            }
+            if record.last() == Some(&options.delimiter) {
+                record.pop();
+            }
+            process(&record)?;
+        }
+    };
+
+    if options.inputs.is_empty() {
+        let stdin = io::stdin();
+        read(&mut stdin.lock())?;
+    }
+    for path in &options.inputs {
+        if path == "-" {
+            let stdin = io::stdin();
+            read(&mut stdin.lock())?;
+        } else {
+            let file = File::open(path)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+            read(&mut BufReader::new(file))?;
+        }
+    }
+    Ok(())
+}
+
+// --------------------------------------------------------------------------------
+// Deduplication
+
+/// Prints the records of the inputs that are not duplicates (or only those, if inverted)
+struct Dedup<'a> {
+    options: &'a Options,
+
+    /// Where records are printed
+    output: &'a mut dyn Write,
+
+    /// Snapshot the filter starts from, if any
+    snapshot: Option<&'a [u8]>,
+}
+
+impl<'a> TableVisitor for Dedup<'a> {
+    type Output = io::Result<()>;
+
+    fn visit<P: InsertPolicy>(self, mut filter: Qht<P>) -> io::Result<()> {
+        let options = self.options;
+        if let Some(bytes) = self.snapshot {
+            filter.load_bytes(bytes).map_err(invalid_data)?;
+        }
+
+        let output = self.output;
+        for_each_record(options, |record| {
+            if filter.insert_key(record) == options.invert {
+                output.write_all(record)?;
+                output.write_all(&[options.delimiter])?;
+            }
+            Ok(())
+        })?;
+
+        if let Some(ref path) = options.state {
+            // Replace the snapshot atomically
+            let tmp = format!("{}.tmp", path);
+            fs::write(&tmp, filter.to_bytes())?;
+            fs::rename(&tmp, path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Deduplicates the inputs, starting from the snapshot given by `--state` if it exists
+fn dedup(options: &Options, output: &mut dyn Write) -> io::Result<()> {
+    let snapshot = match options.state {
+        Some(ref path) => match fs::read(path) {
+            Ok(bytes) => Some(bytes),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(io::Error::new(e.kind(), format!("{}: {}", path, e))),
+        },
+        None => None,
+    };
+
+    let mut options = options.clone();
+    let mut flavour = options.flavour.unwrap_or(Flavour::Qht);
+    if let Some(ref bytes) = snapshot {
+        let header = Header::decode_exact(bytes).map_err(invalid_data)?;
+        if options.flavour.map_or(false, |f| f != header.flavour) {
+            return Err(invalid_data(FormatError::BadFlavour(header.flavour as u8)));
+        }
+        flavour = header.flavour;
+        options.memory = header
+            .n_cells
+            .checked_mul(header.n_buckets)
+            .and_then(|n| n.checked_mul(header.fingerprint_size))
+            .ok_or_else(|| invalid_data(FormatError::BadParameters))?;
+        options.buckets = header.n_buckets;
+        options.fingerprint_bits = header.fingerprint_size;
+        options.choices = header.choices;
+    }
+    if flavour == Flavour::Custom {
+        return Err(invalid_data(FormatError::BadFlavour(flavour as u8)));
+    }
+
+    let visitor = Dedup {
+        options: &options,
+        output,
+        snapshot: snapshot.as_ref().map(Vec::as_slice),
+    };
+    with_table(flavour, &options, visitor)
+}
+
+fn invalid_data(e: FormatError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+// --------------------------------------------------------------------------------
+// Evaluation
+
+/// Measurements of a flavour over the evaluated records
+struct Evaluation {
+    /// Answers of the filter against the ground truth
+    confusion: ConfusionMatrix,
+
+    /// Records processed per second
+    throughput: f64,
+
+    /// Memory holding the fingerprints, in bytes
+    memory: usize,
+}
+
+/// Streams records, flagged with whether they are duplicates, through a table
+struct Evaluate<'a> {
+    records: &'a [(Vec<u8>, bool)],
+}
+
+impl<'a> TableVisitor for Evaluate<'a> {
+    type Output = Evaluation;
+
+    fn visit<P: InsertPolicy>(self, mut filter: Qht<P>) -> Evaluation {
+        let mut detected = Vec::with_capacity(self.records.len());
+        let start = Instant::now();
+        for (record, _) in self.records {
+            detected.push(filter.insert_key(record));
+        }
+        let elapsed = start.elapsed();
+
+        let mut evaluation = Evaluation {
+            confusion: ConfusionMatrix::default(),
+            throughput: self.records.len() as f64
+                / (elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9),
+            // The buckets fit in memory
+            memory: filter.header().payload_len().unwrap(),
+        };
+        for (&(_, duplicate), detected) in self.records.iter().zip(detected) {
+            evaluation.confusion.record(detected, duplicate);
+        }
+        evaluation
+    }
+}
+
+/// Prints the false positive and false negative rates of the selected flavours over the inputs
+fn evaluate(options: &Options, output: &mut dyn Write) -> io::Result<()> {
+    // Ground truth is computed once, with an exact set
+    let mut records = Vec::new();
+    for_each_record(options, |record| {
+        records.push(record.to_vec());
+        Ok(())
+    })?;
+    let records: Vec<(Vec<u8>, bool)> = streams::label(records).collect();
+    let duplicates = records.iter().filter(|&&(_, duplicate)| duplicate).count();
+    let unique = records.len() - duplicates;
+
+    writeln!(
+        output,
+        "{} records, {} unique, {} duplicates",
+        records.len(),
+        unique,
+        duplicates
+    )?;
+    writeln!(
+        output,
+        "{:<8} {:>10} {:>10} {:>14} {:>12}",
+        "flavour", "FPR", "FNR", "records/s", "bytes"
+    )?;
+
+    let flavours = match options.flavour {
+        Some(flavour) => vec![flavour],
+        None => FLAVOURS.to_vec(),
+    };
+    for flavour in flavours {
+        let evaluation = with_table(flavour, options, Evaluate { records: &records });
+        writeln!(
+            output,
+            "{:<8} {:>10.6} {:>10.6} {:>14.0} {:>12}",
+            flavour_name(flavour),
+            evaluation.confusion.false_positive_rate(),
+            evaluation.confusion.false_negative_rate(),
+            evaluation.throughput,
+            evaluation.memory
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns `count / total`, or 0 if `total` is 0
+fn rate(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Parameter sweep
+
+/// Generates the stream of `sweep`, each element flagged with whether it is a duplicate
+fn sweep_stream(options: &Options) -> Vec<(Vec<u8>, bool)> {
+    let sweep = &options.sweep;
+    let seed = seeded_rng(options).gen();
+    let universe = sweep.universe as u64;
+    let stream: Box<dyn Iterator<Item = u64>> = match sweep.stream {
+        StreamKind::Uniform => Box::new(Uniform::new(universe, seed)),
+        StreamKind::Zipf => Box::new(Zipf::new(sweep.universe, sweep.exponent, seed)),
+        StreamKind::Bursty => Box::new(Bursty::new(universe, 1000, 100, seed)),
+        StreamKind::Delayed => Box::new(DelayedDuplicates::new(sweep.delay, 0.5, seed)),
+    };
+    streams::label(stream.take(sweep.length))
+        .map(|(e, duplicate)| (e.to_le_bytes().to_vec(), duplicate))
+        .collect()
+}
+
+/// Prints as CSV the error rates of the swept flavours for each combination of parameters
+fn sweep(options: &Options, output: &mut dyn Write) -> io::Result<()> {
+    let records = sweep_stream(options);
+    let duplicates = records.iter().filter(|&&(_, duplicate)| duplicate).count();
+    let stream = options.sweep.stream.name();
+
+    writeln!(
+        output,
+        "stream,flavour,memory_bytes,buckets,fingerprint_bits,records,duplicates,fpr,fnr"
+    )?;
+
+    let flavours = match options.flavour {
+        Some(flavour) => vec![flavour],
+        None => SWEPT_FLAVOURS.to_vec(),
+    };
+    let mut table_options = options.clone();
+    for &memory in &options.sweep.memories {
+        for &buckets in &options.sweep.buckets {
+            for &fingerprint_bits in &options.sweep.fingerprint_bits {
+                table_options.memory = memory;
+                table_options.buckets = buckets;
+                table_options.fingerprint_bits = fingerprint_bits;
+                for &flavour in &flavours {
+                    let evaluation =
+                        with_table(flavour, &table_options, Evaluate { records: &records });
+                    writeln!(
+                        output,
+                        "{},{},{},{},{},{},{},{},{}",
+                        stream,
+                        flavour_name(flavour),
+                        evaluation.memory,
+                        buckets,
+                        fingerprint_bits,
+                        records.len(),
+                        duplicates,
+                        evaluation.confusion.false_positive_rate(),
+                        evaluation.confusion.false_negative_rate()
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// --------------------------------------------------------------------------------
+// Inspection
+
+/// Prints the parameters, occupancy and estimated false positive rate of a snapshot
+fn inspect(options: &Options, output: &mut dyn Write) -> io::Result<()> {
+    let path = &options.inputs[0];
+    let bytes = fs::read(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+    let view = QhtView::new(&bytes).map_err(invalid_data)?;
+    let header = view.header();
+
+    writeln!(output, "flavour:          {}", flavour_name(header.flavour))?;
+    writeln!(output, "cells:            {}", header.n_cells)?;
+    writeln!(output, "buckets:          {}", header.n_buckets)?;
+    writeln!(output, "fingerprint bits: {}", header.fingerprint_size)?;
+    writeln!(output, "choices:          {}", header.choices)?;
+    writeln!(
+        output,
+        "memory:           {} bytes",
+        header.payload_len().map_err(invalid_data)?
+    )?;
+
+    let occupancy = view.occupancy();
+    let used: usize = occupancy
+        .iter()
+        .enumerate()
+        .map(|(load, &cells)| load * cells)
+        .sum();
+    writeln!(
+        output,
+        "load factor:      {:.6}",
+        rate(used, header.n_cells * header.n_buckets)
+    )?;
+    writeln!(output, "estimated FPR:    {:.6}", view.estimated_fpr())?;
+    writeln!(output, "occupancy (non-empty buckets: cells):")?;
+    for (load, &cells) in occupancy.iter().enumerate() {
+        writeln!(
+            output,
+            "{:>6}: {:>12} ({:.2}%)",
+            load,
+            cells,
+            100.0 * rate(cells, header.n_cells)
+        )?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------------------------------------
+
+/// Runs the subcommand, printing its results to `output`
+fn run(options: &Options, output: &mut dyn Write) -> io::Result<()> {
+    match options.command {
+        Command::Dedup => dedup(options, output),
+        Command::Evaluate => evaluate(options, output),
+        Command::Inspect => inspect(options, output),
+        Command::Sweep => sweep(options, output),
+    }?;
+    output.flush()
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(message) => {
+            eprintln!("qht: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let stdout = io::stdout();
+    let mut output = BufWriter::new(stdout.lock());
+    match run(&options, &mut output) {
+        Ok(()) => (),
+        // The reader went away (e.g. `qht | head`)
+        Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => (),
+        Err(e) => {
+            eprintln!("qht: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qht::format::HasherId;
+
+    fn parse(args: &[&str]) -> Result<Option<Options>, String> {
+        parse_args(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn parses_options() {
+        let options = parse(&[
+            "dedup",
+            "--flavour=qqhtd",
+            "--memory",
+            "2K",
+            "--buckets",
+            "3",
+            "--fingerprint-bits=5",
+            "--seed",
+            "7",
+            "--invert",
+            "-z",
+            "a.txt",
+            "-",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(options.command, Command::Dedup);
+        assert_eq!(options.flavour, Some(Flavour::QQhtD));
+        assert_eq!(options.memory, 2048 * 8);
+        assert_eq!(options.buckets, 3);
+        assert_eq!(options.fingerprint_bits, 5);
+        assert_eq!(options.seed, Some(7));
+        assert!(options.invert);
+        assert_eq!(options.delimiter, 0);
+        assert_eq!(options.inputs, vec!["a.txt", "-"]);
+
+        assert!(parse(&["--help"]).unwrap().is_none());
+        assert!(parse(&["--flavour", "bloom"]).is_err());
+        assert!(parse(&["--fingerprint-bits", "9"]).is_err());
+        assert!(parse(&["--memory"]).is_err());
+        assert!(parse(&["--memory", "1", "--buckets", "4"]).is_err());
+    }
+
+    #[test]
+    fn parses_evaluate() {
+        let options = parse(&["evaluate", "--input", "sample.txt"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.command, Command::Evaluate);
+        assert_eq!(options.flavour, None);
+        assert_eq!(options.inputs, vec!["sample.txt"]);
+
+        assert!(parse(&["evaluate"]).is_err());
+        assert!(parse(&["evaluate", "--invert", "sample.txt"]).is_err());
+        assert!(parse(&["--input", "sample.txt"]).is_err());
+    }
+
+    /// Runs the command line `args`, returning what it printed
+    fn run_args(args: &[&str]) -> io::Result<String> {
+        let options = parse(args).unwrap().unwrap();
+        let mut output = Vec::new();
+        run(&options, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    /// Returns a path in the temporary directory, unique to this test process
+    fn temp_path(name: &str) -> String {
+        let path = env::temp_dir().join(format!("qht-{}-{}", name, process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn dedup_prints_unique_records() {
+        let (first, second) = (temp_path("first"), temp_path("second"));
+        fs::write(&first, "a\nb\na\nc\n").unwrap();
+        fs::write(&second, "c\nd\nb").unwrap();
+
+        let args = ["--memory", "1K", "--seed", "1", &first, &second];
+        assert_eq!(run_args(&args).unwrap(), "a\nb\nc\nd\n");
+        let output = run_args(&[&["--invert", "--flavour", "lru"], &args[..]].concat()).unwrap();
+        assert_eq!(output, "a\nc\nb\n");
+
+        fs::write(&first, "a\0b\0a\0").unwrap();
+        assert_eq!(run_args(&["-z", &first]).unwrap(), "a\0b\0");
+
+        let error = run_args(&[&temp_path("missing")]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+
+        for path in &[first, second] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn evaluate_reports_each_flavour() {
+        let input = temp_path("evaluate");
+        fs::write(&input, "a\nb\na\nc\nb\n").unwrap();
+
+        let output = run_args(&["evaluate", "--memory", "1K", "--seed", "1", &input]).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "5 records, 3 unique, 2 duplicates");
+        assert_eq!(lines.len(), 2 + FLAVOURS.len());
+        for (line, &flavour) in lines[2..].iter().zip(FLAVOURS.iter()) {
+            // A small stream fits in the filters
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            assert_eq!(fields[..3], [flavour_name(flavour), "0.000000", "0.000000"]);
+            assert_eq!(fields[4], "1024");
+        }
+
+        let output = run_args(&["evaluate", "--flavour", "clock", &input]).unwrap();
+        assert_eq!(output.lines().count(), 3);
+        fs::remove_file(&input).unwrap();
+    }
+
+    #[test]
+    fn parses_state_and_inspect() {
+        let options = parse(&["--state", "s.bin", "a.txt"]).unwrap().unwrap();
+        assert_eq!(options.state, Some("s.bin".to_string()));
+        assert_eq!(options.inputs, vec!["a.txt"]);
+
+        let options = parse(&["inspect", "s.bin"]).unwrap().unwrap();
+        assert_eq!(options.command, Command::Inspect);
+        assert_eq!(options.inputs, vec!["s.bin"]);
+
+        assert!(parse(&["inspect"]).is_err());
+        assert!(parse(&["evaluate", "--state", "s.bin", "a.txt"]).is_err());
+    }
+
+    #[test]
+    fn dedup_resumes_from_state() {
+        let (first, second, state) = (temp_path("first"), temp_path("second"), temp_path("state"));
+        fs::write(&first, "a\nb\na\nc\n").unwrap();
+        fs::write(&second, "c\nd\nb\n").unwrap();
+
+        let args = ["--memory", "1K", "--seed", "1", "--state", &state];
+        let output = run_args(&[&args[..], &[first.as_str()]].concat()).unwrap();
+        assert_eq!(output, "a\nb\nc\n");
+        let output = run_args(&[&["--invert"], &args[..], &[second.as_str()]].concat()).unwrap();
+        assert_eq!(output, "c\nb\n");
+
+        // The snapshot holds the parameters and the 4 distinct records
+        let output = run_args(&["inspect", &state]).unwrap();
+        assert!(output.starts_with("flavour:          qht\ncells:            256\n"));
+        assert!(output.contains("load factor:      0.003906\n"));
+
+        // Its flavour cannot be overridden
+        let error = run_args(&["--flavour", "lru", "--state", &state, &first]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        for path in &[first, second, state] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn corrupt_snapshots_are_rejected() {
+        // A header whose size overflows, followed by a few bytes
+        let header = Header {
+            flavour: Flavour::Qht,
+            hasher: HasherId::Fnv1a,
+            n_cells: 1 << 61,
+            n_buckets: 1,
+            fingerprint_size: 8,
+            choices: 1,
+        };
+        let mut bytes = header.encode().to_vec();
+        bytes.extend_from_slice(&[0; 64]);
+        let path = temp_path("corrupt");
+        fs::write(&path, &bytes).unwrap();
+
+        let error = run_args(&["--state", &path, &path]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let error = run_args(&["inspect", &path]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn parses_sweep() {
+        let options = parse(&[
+            "sweep",
+            "--memory=1K,4K",
+            "--buckets",
+            "2, 4",
+            "--stream",
+            "zipf",
+            "--length",
+            "100",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(options.command, Command::Sweep);
+        assert_eq!(options.sweep.memories, vec![1024 * 8, 4096 * 8]);
+        assert_eq!(options.sweep.buckets, vec![2, 4]);
+        assert_eq!(options.sweep.fingerprint_bits, vec![8]);
+        assert_eq!(options.sweep.stream, StreamKind::Zipf);
+        assert_eq!(options.sweep.length, 100);
+
+        assert!(parse(&["sweep", "--buckets", "4,0"]).is_err());
+        assert!(parse(&["sweep", "--memory", "1,1K"]).is_err());
+        assert!(parse(&["sweep", "--stream", "poisson"]).is_err());
+        assert!(parse(&["sweep", "--delay", "0"]).is_err());
+        assert!(parse(&["sweep", "a.txt"]).is_err());
+    }
+
+    #[test]
+    fn sweep_stream_is_reproducible() {
+        let mut options = parse(&["sweep", "--length", "1000", "--universe", "500"])
+            .unwrap()
+            .unwrap();
+        options.seed = Some(3);
+        let stream = sweep_stream(&options);
+        assert_eq!(stream, sweep_stream(&options));
+        assert_eq!(stream.len(), 1000);
+
+        let duplicates = stream.iter().filter(|&&(_, duplicate)| duplicate).count();
+        assert!(duplicates >= 500);
+    }
+
+    #[test]
+    fn sweep_prints_each_combination() {
+        let args = [
+            "sweep",
+            "--memory=1K,2K",
+            "--buckets=1,4",
+            "--length=1000",
+            "--universe=500",
+            "--seed=3",
+        ];
+        let output = run_args(&args).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1 + 2 * 2 * SWEPT_FLAVOURS.len());
+        assert!(lines[1].starts_with("uniform,qht,1024,1,8,1000,"));
+        assert!(lines[12].starts_with("uniform,qqhtd,2048,4,8,1000,"));
+        assert_eq!(output, run_args(&args).unwrap());
+    }
+}
@@ -0,0 +1,275 @@
+use crate::basicqht::*;
+use crate::hash::{key_hash, DefaultBuildHasher};
+use crate::lru::LruRefresh;
+use crate::policy::InsertPolicy;
+use crate::storage::{CellStorage, DefaultStorage, OwnedCellStorage};
+use crate::table::Qht;
+
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
+
+/// Largest supported value size, in bits
+pub const VALUE_SIZE_LIMIT: usize = 32;
+
+// --------------------------------------------------------------------------------
+// Values
+
+/// A value that can be stored in the buckets of a `QhtMap`
+pub trait MapValue: Copy {
+    /// Returns the bits representing the value
+    fn to_bits(self) -> u64;
+
+    /// Returns the value represented by `bits`
+    fn from_bits(bits: u64) -> Self;
+}
+
+macro_rules! impl_map_value {
+    ($($t:ty),*) => {
+        $(
+            impl MapValue for $t {
+                fn to_bits(self) -> u64 {
+                    u64::from(self)
+                }
+
+                fn from_bits(bits: u64) -> Self {
+                    bits as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_map_value!(u8, u16, u32, u64);
+
+impl MapValue for bool {
+    fn to_bits(self) -> u64 {
+        u64::from(self)
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        bits != 0
+    }
+}
+
+// --------------------------------------------------------------------------------
+
+/// Associative Quotient Hash Table ("compact")
+///
+/// Each bucket holds a fingerprint followed by a value of `value_size` bits, so that elements can be
+/// mapped to a small payload within a bounded memory. Cells are managed by an `InsertPolicy`, as in
+/// `Qht`; by default, the least recently inserted element of a cell is evicted (see `LruRefresh`).
+///
+/// Lookups are approximate: an element may be mapped to the value of another element sharing its
+/// address and fingerprint, and evicted elements are forgotten.
+///
+/// # Example
+/// ```rust
+/// use qht::{Element, QhtMap};
+/// let mut m = QhtMap::<u8>::new(1024, 4, 6, 2);
+/// let e = Element { value: 1234 };
+/// assert_eq!( m.get(e), None );
+/// assert_eq!( m.insert(e, 3), None );
+/// assert_eq!( m.insert(e, 1), Some(3) );
+/// assert_eq!( m.get(e), Some(1) );
+/// ```
+pub struct QhtMap<V, P = LruRefresh, S = DefaultStorage, H = DefaultBuildHasher> {
+    /// Underlying table, storing values after fingerprints
+    table: Qht<P, S, H>,
+
+    /// Size of the value (in bits)
+    value_size: usize,
+
+    /// Type of the values
+    values: PhantomData<V>,
+}
+
+#[cfg(feature = "std")]
+impl<V: MapValue, P: InsertPolicy + Default> QhtMap<V, P> {
+    /// Returns a newly created `QhtMap` or panics
+    ///
+    /// This function takes as arguments:
+    /// * `memory_size`: allocated memory for the map, in bits
+    /// * `n_buckets`: number of buckets
+    /// * `fingerprint_size`: size of each fingerprint, in bits. Cannot exceed `FINGERPRINT_SIZE_LIMIT`.
+    /// * `value_size`: size of each value, in bits. Cannot exceed `VALUE_SIZE_LIMIT`.
+    ///
+    /// Parameters should be chosen in a consistent way, namely so that
+    /// `memory_size` >= `n_buckets` * (`fingerprint_size` + `value_size`)
+    pub fn new(
+        memory_size: usize,
+        n_buckets: usize,
+        fingerprint_size: usize,
+        value_size: usize,
+    ) -> Self {
+        Self::with_hasher(
+            memory_size,
+            n_buckets,
+            fingerprint_size,
+            value_size,
+            DefaultBuildHasher::default(),
+            P::default(),
+        )
+    }
+}
+
+impl<V: MapValue, P: InsertPolicy, S: OwnedCellStorage, H: BuildHasher> QhtMap<V, P, S, H> {
+    /// Returns a newly created `QhtMap` using the provided hasher and policy, or panics
+    ///
+    /// Other arguments are the same as for `QhtMap::new`.
+    pub fn with_hasher(
+        memory_size: usize,
+        n_buckets: usize,
+        fingerprint_size: usize,
+        value_size: usize,
+        hasher: H,
+        policy: P,
+    ) -> Self {
+        check_parameters(n_buckets, fingerprint_size);
+
+        // Value size is limited
+        if value_size > VALUE_SIZE_LIMIT {
+            panic!("[QHT Map] Incorrect parameters, value_size cannot exceed 32.");
+        } else if value_size == 0 {
+            panic!("[QHT Map] Incorrect parameters, value_size cannot be zero.");
+        }
+
+        let n_cells = memory_size / (n_buckets * (fingerprint_size + value_size));
+
+        // There should be at least one cell
+        if n_cells == 0 {
+            panic!("[QHT Map] Incorrect parameters, memory size should be at least n_buckets * (fingerprint_size + value_size)");
+        }
+
+        Self {
+            table: Qht::with_payload(
+                n_cells,
+                n_buckets,
+                fingerprint_size,
+                value_size,
+                hasher,
+                policy,
+            ),
+            value_size,
+            values: PhantomData,
+        }
+    }
+}
+
+impl<V: MapValue, P: InsertPolicy, S: CellStorage, H: BuildHasher> QhtMap<V, P, S, H> {
+    /// Gives each element `choices` candidate cells instead of one, or panics
+    ///
+    /// See `Qht::with_choices`.
+    pub fn with_choices(mut self, choices: usize) -> Self {
+        self.table = self.table.with_choices(choices);
+        self
+    }
+
+    /// Returns the value mapped to `e`, if any
+    pub fn get(&self, e: impl Hash) -> Option<V> {
+        self.get_hash(self.table.hash(e))
+    }
+
+    /// Maps `e` to `value`, as decided by the policy
+    ///
+    /// Returns the value previously mapped to `e`, if any. Panics if `value` does not fit in `value_size` bits.
+    pub fn insert(&mut self, e: impl Hash, value: V) -> Option<V> {
+        let hash = self.table.hash(e);
+        self.insert_hash(hash, value)
+    }
+
+    /// Returns the value mapped to the element of hash `hash`, if any (see `Qht::lookup_hash`)
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::QhtMap;
+    /// let mut m = QhtMap::<u8>::new(1024, 4, 8, 8);
+    /// let hash = 0x9e37_79b9_7f4a_7c15;
+    /// assert_eq!( m.insert_hash(hash, 42), None );
+    /// assert_eq!( m.get_hash(hash), Some(42) );
+    /// ```
+    pub fn get_hash(&self, hash: u64) -> Option<V> {
+        self.table.payload(hash).map(V::from_bits)
+    }
+
+    /// Maps the element of hash `hash` to `value`, as decided by the policy
+    ///
+    /// See `get_hash` and `insert`.
+    pub fn insert_hash(&mut self, hash: u64, value: V) -> Option<V> {
+        let bits = value.to_bits();
+        if bits >> self.value_size != 0 {
+            panic!("[QHT Map] Value does not fit in value_size bits.");
+        }
+        self.table.insert_payload(hash, |_| bits).map(V::from_bits)
+    }
+
+    /// Returns the value mapped to a byte-string key, if any (see `Qht::lookup_key`)
+    pub fn get_key(&self, key: impl AsRef<[u8]>) -> Option<V> {
+        self.get_hash(key_hash(key))
+    }
+
+    /// Maps a byte-string key to `value`, as decided by the policy
+    ///
+    /// See `get_key` and `insert`.
+    pub fn insert_key(&mut self, key: impl AsRef<[u8]>, value: V) -> Option<V> {
+        self.insert_hash(key_hash(key), value)
+    }
+
+    /// Returns the value mapped to the element of 128-bit hash `hash`, if any (see
+    /// `Qht::lookup_hash128`)
+    pub fn get_hash128(&self, hash: u128) -> Option<V> {
+        self.get_hash(fold_hash(hash))
+    }
+
+    /// Maps the element of 128-bit hash `hash` to `value`, as decided by the policy
+    ///
+    /// See `get_hash128` and `insert`.
+    pub fn insert_hash128(&mut self, hash: u128, value: V) -> Option<V> {
+        self.insert_hash(fold_hash(hash), value)
+    }
+
+    /// Returns the insertion policy
+    pub fn policy(&self) -> &P {
+        self.table.policy()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::QhtMap;
+    use crate::{Clock, FifoShift, InsertPolicy, LruRefresh, QueuedRandomEviction, RandomEviction};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::HashMap;
+
+    /// Checks that a map returns the last value of inserted elements
+    ///
+    /// `exact` policies keep a single copy of each fingerprint, so that no element is evicted here.
+    fn check_map<P: InsertPolicy + Default>(exact: bool) {
+        let mut m = QhtMap::<u16, P>::new(50_000, 4, 8, 10);
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut values = HashMap::new();
+        for _ in 0..2000 {
+            let (e, value) = (rng.gen_range(0, 200u64), rng.gen_range(0, 1024u16));
+            let previous = m.insert(e, value);
+            if exact {
+                assert_eq!(previous, values.insert(e, value));
+            }
+            assert_eq!(m.get(e), Some(value));
+        }
+        if exact {
+            for (&e, &value) in &values {
+                assert_eq!(m.get(e), Some(value));
+            }
+        }
+        assert_eq!(m.get(5000u64), None);
+    }
+
+    #[test]
+    fn map_follows_policies() {
+        check_map::<LruRefresh>(true);
+        check_map::<RandomEviction>(true);
+        check_map::<Clock>(true);
+        check_map::<QueuedRandomEviction>(false);
+        check_map::<FifoShift>(false);
+    }
+}
@@ -0,0 +1,275 @@
+use crate::basicqht::{cells_for_memory, Fingerprint};
+use crate::format::{read_bits, write_bits, FormatError, HasherId, Header, HEADER_LEN};
+use crate::policy::InsertPolicy;
+use crate::storage::CellStorage;
+use crate::table::Qht;
+
+use memmap::{MmapMut, MmapOptions};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+// --------------------------------------------------------------------------------
+// Memory-mapped storage
+
+/// A `CellStorage` backed by a memory-mapped file
+///
+/// The file holds a filter in the crate's serialized format (see `format`), so that it can be
+/// reopened after a restart without reading it. Flavours provide `create`, `open`,
+/// `open_read_only` and `sync` when operating over a `MmapStorage`.
+///
+/// # Example
+/// ```rust
+/// use qht::{Element, Filter, QuotientHashTable};
+/// let path = std::env::temp_dir().join("qht-mmap-example.bin");
+///
+/// let mut f = QuotientHashTable::create(&path, 1024, 4, 3).unwrap();
+/// f.insert(Element { value: 1234 });
+/// f.sync().unwrap();
+/// drop(f);
+///
+/// let f = QuotientHashTable::open_read_only(&path).unwrap();
+/// assert!( f.lookup(Element { value: 1234 }) );
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct MmapStorage {
+    /// Mapping of the whole file, header included
+    map: MmapMut,
+
+    /// Parameters read from the header
+    header: Header,
+
+    /// Whether changes are written back to the file
+    writable: bool,
+}
+
+impl MmapStorage {
+    /// Creates (or truncates) a file holding an empty filter described by `header`, and maps it
+    pub fn create<P: AsRef<Path>>(path: P, header: Header) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(header.total_len().map_err(invalid_data)? as u64)?;
+
+        let mut map = unsafe { MmapOptions::new().map_mut(&file)? };
+        map[..HEADER_LEN].copy_from_slice(&header.encode());
+
+        Ok(Self {
+            map,
+            header,
+            writable: true,
+        })
+    }
+
+    /// Maps an existing file, validating its header
+    ///
+    /// Changes are written back to the file
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let map = unsafe { MmapOptions::new().map_mut(&file)? };
+        Self::from_map(map, true)
+    }
+
+    /// Maps an existing file, validating its header
+    ///
+    /// The file is only opened for reading and can be shared by several processes: changes,
+    /// if any, remain private to this mapping and are never written back.
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let map = unsafe { MmapOptions::new().map_copy(&file)? };
+        Self::from_map(map, false)
+    }
+
+    fn from_map(map: MmapMut, writable: bool) -> io::Result<Self> {
+        let header = Header::decode_exact(&map).map_err(invalid_data)?;
+        Ok(Self {
+            map,
+            header,
+            writable,
+        })
+    }
+
+    /// Returns the parameters read from the file header
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Flushes outstanding changes to the file
+    pub fn sync(&self) -> io::Result<()> {
+        if !self.writable {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "[MmapStorage] Filter was opened read-only",
+            ));
+        }
+        self.map.flush()
+    }
+}
+
+impl CellStorage for MmapStorage {
+    fn capacity(&self, _width: usize) -> usize {
+        self.header.n_cells * self.header.n_buckets
+    }
+
+    fn get_bucket(&self, index: usize, width: usize) -> Fingerprint {
+        read_bits(&self.map[HEADER_LEN..], index * width, width)
+    }
+
+    fn set_bucket(&mut self, index: usize, width: usize, value: Fingerprint) {
+        write_bits(&mut self.map[HEADER_LEN..], index * width, width, value)
+    }
+
+    fn clear(&mut self) {
+        for byte in self.map[HEADER_LEN..].iter_mut() {
+            *byte = 0;
+        }
+    }
+
+    /// Writes `choices` to the header, so that the filter is reopened with it
+    fn set_choices(&mut self, choices: usize) {
+        self.header.choices = choices;
+        self.map[..HEADER_LEN].copy_from_slice(&self.header.encode());
+    }
+
+    /// The number of cells is fixed by the file length
+    fn resizable(&self) -> bool {
+        false
+    }
+}
+
+/// Converts a format error into an I/O error
+fn invalid_data(e: FormatError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+// --------------------------------------------------------------------------------
+// File-backed tables
+
+impl<P: InsertPolicy + Default> Qht<P, MmapStorage> {
+    /// Creates a file-backed filter at `path`, truncating any existing file
+    ///
+    /// Arguments are the same as for `Qht::new`.
+    pub fn create<Q: AsRef<Path>>(
+        path: Q,
+        memory_size: usize,
+        n_buckets: usize,
+        fingerprint_size: usize,
+    ) -> io::Result<Self> {
+        let header = Header {
+            flavour: P::FLAVOUR,
+            hasher: HasherId::Fnv1a,
+            n_cells: cells_for_memory(memory_size, n_buckets, fingerprint_size),
+            n_buckets,
+            fingerprint_size,
+            choices: 1,
+        };
+        let storage = MmapStorage::create(path, header)?;
+        Ok(Self::with_storage(storage, n_buckets, fingerprint_size))
+    }
+
+    /// Opens a file-backed filter, whose changes are written back to the file
+    ///
+    /// Fails if the file does not hold a filter of this flavour
+    pub fn open<Q: AsRef<Path>>(path: Q) -> io::Result<Self> {
+        Self::from_mmap(MmapStorage::open(path)?)
+    }
+
+    /// Opens a file-backed filter without write access to the file
+    ///
+    /// Fails if the file does not hold a filter of this flavour
+    pub fn open_read_only<Q: AsRef<Path>>(path: Q) -> io::Result<Self> {
+        Self::from_mmap(MmapStorage::open_read_only(path)?)
+    }
+
+    /// Flushes outstanding changes to the file
+    pub fn sync(&self) -> io::Result<()> {
+        self.qht.sync()
+    }
+
+    fn from_mmap(storage: MmapStorage) -> io::Result<Self> {
+        let header = *storage.header();
+        if header.flavour != P::FLAVOUR {
+            return Err(invalid_data(FormatError::BadFlavour(header.flavour as u8)));
+        }
+        if header.hasher != HasherId::Fnv1a {
+            return Err(invalid_data(FormatError::BadHasher(header.hasher as u8)));
+        }
+        Ok(
+            Self::with_storage(storage, header.n_buckets, header.fingerprint_size)
+                .with_choices(header.choices),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Filter, QQuotientHashTableD, QuotientHashTable};
+
+    #[test]
+    fn mmap_survives_reopening() {
+        let path = std::env::temp_dir().join(format!("qht-test-{}.bin", std::process::id()));
+
+        let mut f = QQuotientHashTableD::create(&path, 4096, 4, 5).unwrap();
+        let mut reference = QQuotientHashTableD::new(4096, 4, 5);
+        for value in 0..500u64 {
+            assert_eq!(f.insert(value), reference.insert(value));
+        }
+        f.sync().unwrap();
+        drop(f);
+
+        let mut f = QQuotientHashTableD::open(&path).unwrap();
+        for value in 0..1000u64 {
+            assert_eq!(f.lookup(value), reference.lookup(value));
+        }
+        f.insert(5000u64);
+        reference.insert(5000u64);
+        drop(f);
+
+        // Read-only filters accept insertions but never write them back
+        let mut f = QQuotientHashTableD::open_read_only(&path).unwrap();
+        assert!(f.lookup(5000u64));
+        f.insert(6000u64);
+        assert!(f.sync().is_err());
+        drop(f);
+        let f = QQuotientHashTableD::open_read_only(&path).unwrap();
+        for value in 0..7000u64 {
+            assert_eq!(f.lookup(value), reference.lookup(value));
+        }
+        drop(f);
+
+        // Header is validated
+        assert!(QuotientHashTable::open(&path).is_err());
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(40).unwrap();
+        assert!(QQuotientHashTableD::open(&path).is_err());
+
+        // Choices are recorded in the header
+        let mut f = QQuotientHashTableD::create(&path, 4096, 4, 5)
+            .unwrap()
+            .with_choices(2);
+        let mut reference = QQuotientHashTableD::new(4096, 4, 5).with_choices(2);
+        for value in 0..1000u64 {
+            assert_eq!(f.insert(value), reference.insert(value));
+        }
+        drop(f);
+        let f = QQuotientHashTableD::open(&path).unwrap();
+        assert_eq!(f.choices(), 2);
+        for value in 0..2000u64 {
+            assert_eq!(f.lookup(value), reference.lookup(value));
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "number of cells of this storage is fixed")]
+    fn mmap_tables_have_fixed_cells() {
+        let path = std::env::temp_dir().join(format!("qht-cells-{}.bin", std::process::id()));
+        let f = QuotientHashTable::create(&path, 1000 * 5, 1, 5).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        f.with_power_of_two_cells();
+    }
+}
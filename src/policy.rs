@@ -0,0 +1,120 @@
+use crate::basicqht::Fingerprint;
+use crate::format::Flavour;
+use crate::storage::CellStorage;
+use crate::table::Cell;
+
+// --------------------------------------------------------------------------------
+// Insertion policies
+
+/// An `InsertPolicy` decides where a `Qht` stores fingerprints, and which ones it evicts
+///
+/// Built-in policies are `RandomEviction` (QHTc), `QueuedRandomEviction` (QQHTc), `FifoShift` (QQHTDc),
+/// `LruRefresh` and `Clock`.
+///
+/// # Example
+/// ```rust
+/// use qht::{Cell, CellStorage, Filter, InsertPolicy, Qht};
+///
+/// /// Always overwrites the first bucket
+/// #[derive(Default)]
+/// struct FirstBucket;
+///
+/// impl InsertPolicy for FirstBucket {
+///     fn insert<S: CellStorage>(
+///         &mut self,
+///         cell: &mut Cell<S>,
+///         fingerprint: u64,
+///         _position: Option<usize>,
+///     ) -> Option<usize> {
+///         cell.set(0, fingerprint);
+///         Some(0)
+///     }
+/// }
+///
+/// let mut f = Qht::<FirstBucket>::new(1024, 4, 3);
+/// assert!( !f.insert(1234u64) );
+/// assert!( f.lookup(1234u64) );
+/// ```
+pub trait InsertPolicy {
+    /// Flavour recorded when serializing filters using this policy
+    const FLAVOUR: Flavour = Flavour::Custom;
+
+    /// Prepares the policy for a table of `n_cells` cells of `n_buckets` buckets
+    ///
+    /// Called when the table is created or loaded, before any insertion. Policies keeping
+    /// per-cell or per-bucket state should (re)initialise it here.
+    fn init(&mut self, _n_cells: usize, _n_buckets: usize) {}
+
+    /// Inserts `fingerprint` in `cell`
+    ///
+    /// `position` is the first bucket already holding `fingerprint`, if any.
+    /// Returns the bucket holding `fingerprint` afterwards, or `None` if it was not stored.
+    fn insert<S: CellStorage>(
+        &mut self,
+        cell: &mut Cell<S>,
+        fingerprint: Fingerprint,
+        position: Option<usize>,
+    ) -> Option<usize>;
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::streams;
+    use crate::testing::error_rates;
+    use crate::{
+        ClockQuotientHashTable, LruQuotientHashTable, QQuotientHashTable, QQuotientHashTableD,
+        QuotientHashTable,
+    };
+
+    #[test]
+    fn recency_policies_on_zipf_stream() {
+        let stream: Vec<u64> = streams::Zipf::new(100_000, 1.1, 5).take(200_000).collect();
+        let (memory, n_buckets, fingerprint_size) = (40_000, 8, 8);
+
+        let qht = error_rates(
+            QuotientHashTable::new(memory, n_buckets, fingerprint_size),
+            &stream,
+        );
+        let qqht = error_rates(
+            QQuotientHashTable::new(memory, n_buckets, fingerprint_size),
+            &stream,
+        );
+        let qqhtd = error_rates(
+            QQuotientHashTableD::new(memory, n_buckets, fingerprint_size),
+            &stream,
+        );
+        let lru = error_rates(
+            LruQuotientHashTable::new(memory, n_buckets, fingerprint_size),
+            &stream,
+        );
+        let clock = error_rates(
+            ClockQuotientHashTable::new(memory, n_buckets, fingerprint_size),
+            &stream,
+        );
+
+        // Popular elements are never evicted, hence fewer false negatives
+        assert!(lru.1 < qht.1);
+        assert!(lru.1 < qqht.1);
+        assert!(lru.1 < qqhtd.1);
+
+        // Referenced fingerprints get a second chance, which beats random eviction
+        assert!(clock.1 < qht.1);
+        assert!(clock.1 < qqht.1);
+    }
+
+    #[test]
+    fn distant_duplicates_are_forgotten() {
+        // 1000 fingerprints: recent duplicates are caught, distant ones mostly evicted
+        let (memory, n_buckets, fingerprint_size) = (8000, 4, 8);
+        let mut rates = Vec::new();
+        for &delay in &[100, 10_000] {
+            let stream: Vec<u64> = streams::DelayedDuplicates::new(delay, 0.5, 21)
+                .take(100_000)
+                .collect();
+            let f = QQuotientHashTableD::new(memory, n_buckets, fingerprint_size);
+            rates.push(error_rates(f, &stream));
+        }
+        assert!(rates[0].1 < 0.1);
+        assert!(rates[1].1 > 0.5);
+    }
+}
@@ -0,0 +1,129 @@
+// --------------------------------------------------------------------------------
+// Vectorised fingerprint matching
+
+/// Minimal number of buckets per cell for which the vectorised path is used
+pub const SIMD_MIN_BUCKETS: usize = 16;
+
+/// Number of fingerprints unpacked and compared at once
+pub const SIMD_CHUNK: usize = 64;
+
+/// Checks whether `needle` appears in `haystack`
+///
+/// The implementation is chosen at runtime: AVX2 or SSE2 on x86_64, a scalar loop elsewhere.
+/// Without the `std` feature, AVX2 is only used if enabled at compile time.
+///
+/// # Example
+/// ```rust
+/// use qht::simd::contains;
+/// assert!( contains(&[1, 2, 3, 4], 3) );
+/// assert!( !contains(&[1, 2, 3, 4], 5) );
+/// ```
+pub fn contains(haystack: &[u8], needle: u8) -> bool {
+    position(haystack, needle).is_some()
+}
+
+/// Returns the index of the first occurrence of `needle` in `haystack`, if any
+///
+/// The implementation is chosen as for `contains`.
+///
+/// # Example
+/// ```rust
+/// use qht::simd::position;
+/// assert_eq!( position(&[1, 2, 3, 3], 3), Some(2) );
+/// assert_eq!( position(&[1, 2, 3, 4], 5), None );
+/// ```
+pub fn position(haystack: &[u8], needle: u8) -> Option<usize> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        #[cfg(feature = "std")]
+        let avx2 = is_x86_feature_detected!("avx2");
+        #[cfg(not(feature = "std"))]
+        let avx2 = cfg!(target_feature = "avx2");
+
+        if avx2 {
+            return unsafe { position_avx2(haystack, needle) };
+        }
+        // SSE2 is part of the x86_64 baseline
+        unsafe { position_sse2(haystack, needle) }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    position_scalar(haystack, needle)
+}
+
+/// Checks whether `needle` appears in `haystack`, one byte at a time
+///
+/// This is the portable fallback of `contains`
+pub fn contains_scalar(haystack: &[u8], needle: u8) -> bool {
+    haystack.contains(&needle)
+}
+
+/// Returns the index of the first occurrence of `needle` in `haystack`, one byte at a time
+///
+/// This is the portable fallback of `position`
+pub fn position_scalar(haystack: &[u8], needle: u8) -> Option<usize> {
+    haystack.iter().position(|&b| b == needle)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn position_sse2(haystack: &[u8], needle: u8) -> Option<usize> {
+    use core::arch::x86_64::*;
+
+    let broadcast = _mm_set1_epi8(needle as i8);
+    let mut chunks = haystack.chunks_exact(16);
+    let mut offset = 0;
+    for chunk in &mut chunks {
+        let v = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+        let matches = _mm_movemask_epi8(_mm_cmpeq_epi8(v, broadcast));
+        if matches != 0 {
+            return Some(offset + matches.trailing_zeros() as usize);
+        }
+        offset += 16;
+    }
+    position_scalar(chunks.remainder(), needle).map(|idx| offset + idx)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn position_avx2(haystack: &[u8], needle: u8) -> Option<usize> {
+    use core::arch::x86_64::*;
+
+    let broadcast = _mm256_set1_epi8(needle as i8);
+    let mut chunks = haystack.chunks_exact(32);
+    let mut offset = 0;
+    for chunk in &mut chunks {
+        let v = _mm256_loadu_si256(chunk.as_ptr() as *const __m256i);
+        let matches = _mm256_movemask_epi8(_mm256_cmpeq_epi8(v, broadcast));
+        if matches != 0 {
+            return Some(offset + matches.trailing_zeros() as usize);
+        }
+        offset += 32;
+    }
+    position_sse2(chunks.remainder(), needle).map(|idx| offset + idx)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn simd_contains_matches_scalar() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for len in 0..200 {
+            let haystack: Vec<u8> = (0..len).map(|_| rng.gen_range(1, 32)).collect();
+            for needle in 0..40 {
+                assert_eq!(
+                    contains(&haystack, needle),
+                    contains_scalar(&haystack, needle)
+                );
+                assert_eq!(
+                    position(&haystack, needle),
+                    position_scalar(&haystack, needle)
+                );
+            }
+        }
+    }
+}
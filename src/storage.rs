@@ -0,0 +1,308 @@
+use crate::basicqht::Fingerprint;
+
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+pub use rust_dense_bitset::{BitSet, DenseBitSetExtended};
+
+/// Storage used by filters unless another one is provided
+///
+/// This is a `DenseBitSetExtended` when the `std` feature is enabled, and a word-aligned `Vec<u64>` otherwise.
+#[cfg(feature = "std")]
+pub type DefaultStorage = DenseBitSetExtended;
+
+/// Storage used by filters unless another one is provided
+///
+/// This is a `DenseBitSetExtended` when the `std` feature is enabled, and a word-aligned `Vec<u64>` otherwise.
+#[cfg(not(feature = "std"))]
+pub type DefaultStorage = Vec<u64>;
+
+// --------------------------------------------------------------------------------
+// Cell storage
+
+/// A `CellStorage` holds the buckets of a QHT
+///
+/// Buckets are addressed by their global index (`address * n_buckets + bucket_number`),
+/// so that the buckets of a cell are consecutive. Each bucket holds `width` bits.
+pub trait CellStorage {
+    /// Returns the number of buckets of `width` bits the storage can hold (its length)
+    fn capacity(&self, width: usize) -> usize;
+
+    /// Reads the content of a bucket
+    fn get_bucket(&self, index: usize, width: usize) -> Fingerprint;
+
+    /// Writes the content of a bucket
+    fn set_bucket(&mut self, index: usize, width: usize, value: Fingerprint);
+
+    /// Reads `cell.len()` consecutive buckets, starting with bucket `first`
+    fn load_cell(&self, first: usize, width: usize, cell: &mut [Fingerprint]) {
+        for (idx, value) in cell.iter_mut().enumerate() {
+            *value = self.get_bucket(first + idx, width);
+        }
+    }
+
+    /// Writes `cell.len()` consecutive buckets, starting with bucket `first`
+    fn store_cell(&mut self, first: usize, width: usize, cell: &[Fingerprint]) {
+        for (idx, &value) in cell.iter().enumerate() {
+            self.set_bucket(first + idx, width, value);
+        }
+    }
+
+    /// Empties all buckets
+    fn clear(&mut self);
+
+    /// Records the number of candidate cells per element of the table (see `Qht::with_choices`)
+    ///
+    /// Storages persisting the parameters of their table, such as `MmapStorage`, write it back;
+    /// others ignore it.
+    fn set_choices(&mut self, _choices: usize) {}
+
+    /// Returns whether a table may address fewer cells than the storage holds
+    ///
+    /// Storages persisting the number of cells of their table, such as `MmapStorage`, do not
+    /// allow it.
+    fn resizable(&self) -> bool {
+        true
+    }
+}
+
+/// A `CellStorage` which can be allocated by the filter itself
+pub trait OwnedCellStorage: CellStorage {
+    /// Returns an empty storage holding (at least) `n_buckets` buckets of `width` bits
+    fn with_buckets(n_buckets: usize, width: usize) -> Self;
+}
+
+// --------------------------------------------------------------------------------
+// Packed bitset layout
+
+#[cfg(feature = "std")]
+impl CellStorage for DenseBitSetExtended {
+    fn capacity(&self, width: usize) -> usize {
+        self.get_size() / width
+    }
+
+    fn get_bucket(&self, index: usize, width: usize) -> Fingerprint {
+        self.extract_u64(index * width, width)
+    }
+
+    fn set_bucket(&mut self, index: usize, width: usize, value: Fingerprint) {
+        self.insert_u64(value, index * width, width);
+    }
+
+    /// Extracts as many buckets as fit in a word at once
+    fn load_cell(&self, first: usize, width: usize, cell: &mut [Fingerprint]) {
+        let per_word = 64 / width;
+        let mask = bucket_mask(width);
+
+        let mut idx = 0;
+        while idx < cell.len() {
+            let count = core::cmp::min(per_word, cell.len() - idx);
+            let mut word = self.extract_u64((first + idx) * width, count * width);
+            for value in &mut cell[idx..idx + count] {
+                *value = word & mask;
+                word = word.checked_shr(width as u32).unwrap_or(0);
+            }
+            idx += count;
+        }
+    }
+
+    fn clear(&mut self) {
+        let size = self.get_size();
+        self.reset();
+        extend_to(self, size);
+    }
+}
+
+#[cfg(feature = "std")]
+impl OwnedCellStorage for DenseBitSetExtended {
+    fn with_buckets(n_buckets: usize, width: usize) -> Self {
+        let mut bitset = DenseBitSetExtended::with_capacity(n_buckets * width);
+        extend_to(&mut bitset, n_buckets * width);
+        bitset
+    }
+}
+
+/// Grows an empty bitset to `size` (zero) bits
+///
+/// `with_capacity` and `reset` leave the size of the bitset to 0, while its capacity is
+/// computed from its size: writing its last bit sets it.
+#[cfg(feature = "std")]
+fn extend_to(bitset: &mut DenseBitSetExtended, size: usize) {
+    if size > 0 {
+        bitset.insert_u64(0, size - 1, 1);
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Word-aligned layout
+//
+// Buckets never straddle two words: each `u64` holds `64 / width` buckets.
+
+impl CellStorage for [u64] {
+    fn capacity(&self, width: usize) -> usize {
+        <[u64]>::len(self) * (64 / width)
+    }
+
+    fn get_bucket(&self, index: usize, width: usize) -> Fingerprint {
+        let per_word = 64 / width;
+        let shift = (index % per_word) * width;
+        self[index / per_word]
+            .checked_shr(shift as u32)
+            .unwrap_or(0)
+            & bucket_mask(width)
+    }
+
+    fn set_bucket(&mut self, index: usize, width: usize, value: Fingerprint) {
+        let per_word = 64 / width;
+        let shift = (index % per_word) * width;
+        let mask = bucket_mask(width) << shift;
+        let word = &mut self[index / per_word];
+        *word = (*word & !mask) | ((value << shift) & mask);
+    }
+
+    fn clear(&mut self) {
+        for word in self.iter_mut() {
+            *word = 0;
+        }
+    }
+}
+
+impl CellStorage for Vec<u64> {
+    fn capacity(&self, width: usize) -> usize {
+        CellStorage::capacity(self.as_slice(), width)
+    }
+
+    fn get_bucket(&self, index: usize, width: usize) -> Fingerprint {
+        self.as_slice().get_bucket(index, width)
+    }
+
+    fn set_bucket(&mut self, index: usize, width: usize, value: Fingerprint) {
+        self.as_mut_slice().set_bucket(index, width, value)
+    }
+
+    fn clear(&mut self) {
+        CellStorage::clear(self.as_mut_slice())
+    }
+}
+
+impl OwnedCellStorage for Vec<u64> {
+    fn with_buckets(n_buckets: usize, width: usize) -> Self {
+        let per_word = 64 / width;
+        vec![0; (n_buckets + per_word - 1) / per_word]
+    }
+}
+
+impl CellStorage for &mut [u64] {
+    fn capacity(&self, width: usize) -> usize {
+        CellStorage::capacity(&**self, width)
+    }
+
+    fn get_bucket(&self, index: usize, width: usize) -> Fingerprint {
+        (**self).get_bucket(index, width)
+    }
+
+    fn set_bucket(&mut self, index: usize, width: usize, value: Fingerprint) {
+        (**self).set_bucket(index, width, value)
+    }
+
+    fn clear(&mut self) {
+        CellStorage::clear(&mut **self)
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Byte-per-bucket layout
+//
+// Each bucket occupies a whole byte, hence `width` cannot exceed 8.
+
+impl CellStorage for Vec<u8> {
+    fn capacity(&self, width: usize) -> usize {
+        assert!(
+            width <= 8,
+            "[CellStorage] Byte layout cannot hold buckets wider than 8 bits."
+        );
+        Vec::len(self)
+    }
+
+    fn get_bucket(&self, index: usize, _width: usize) -> Fingerprint {
+        Fingerprint::from(self[index])
+    }
+
+    fn set_bucket(&mut self, index: usize, _width: usize, value: Fingerprint) {
+        self[index] = value as u8;
+    }
+
+    fn load_cell(&self, first: usize, _width: usize, cell: &mut [Fingerprint]) {
+        for (value, &byte) in cell.iter_mut().zip(&self[first..]) {
+            *value = Fingerprint::from(byte);
+        }
+    }
+
+    fn clear(&mut self) {
+        for byte in self.iter_mut() {
+            *byte = 0;
+        }
+    }
+}
+
+impl OwnedCellStorage for Vec<u8> {
+    fn with_buckets(n_buckets: usize, width: usize) -> Self {
+        assert!(
+            width <= 8,
+            "[CellStorage] Byte layout cannot hold buckets wider than 8 bits."
+        );
+        vec![0; n_buckets]
+    }
+}
+
+/// Returns a mask covering the `width` lowest bits
+fn bucket_mask(width: usize) -> u64 {
+    if width >= 64 {
+        !0
+    } else {
+        (1 << width) - 1
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{Filter, QQuotientHashTableD};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn storages_agree() {
+        let (memory, n_buckets, fingerprint_size) = (1000, 3, 5);
+        let mut dense = QQuotientHashTableD::new(memory, n_buckets, fingerprint_size);
+        let mut words =
+            QQuotientHashTableD::<Vec<u64>>::new_in(memory, n_buckets, fingerprint_size);
+        let mut bytes = QQuotientHashTableD::<Vec<u8>>::new_in(memory, n_buckets, fingerprint_size);
+        let mut memory = vec![0u64; 100];
+        let mut borrowed =
+            QQuotientHashTableD::with_storage(&mut memory[..], n_buckets, fingerprint_size);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..5000 {
+            // The borrowed slice holds more cells, hence its own addressing
+            let e = rng.gen_range(0, 2000u64);
+            let detected = dense.insert(e);
+            assert_eq!(words.insert(e), detected);
+            assert_eq!(bytes.insert(e), detected);
+            borrowed.insert(e);
+            assert!(borrowed.lookup(e));
+        }
+    }
+
+    #[test]
+    fn owned_bitsets_hold_all_buckets() {
+        // Owned storages hold all their buckets from the start, and keep them once cleared
+        let mut bitset = <DenseBitSetExtended as OwnedCellStorage>::with_buckets(200, 5);
+        assert_eq!(bitset.capacity(5), 200);
+        bitset.set_bucket(199, 5, 31);
+        assert_eq!(bitset.get_bucket(199, 5), 31);
+        bitset.clear();
+        assert_eq!(bitset.capacity(5), 200);
+        assert_eq!(bitset.get_bucket(199, 5), 0);
+    }
+}
@@ -0,0 +1,301 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use std::collections::{HashSet, VecDeque};
+use std::hash::Hash;
+
+// --------------------------------------------------------------------------------
+// Synthetic streams
+//
+// Generators are infinite, seeded iterators of `u64` elements: the same seed always yields the
+// same stream. Use `label` to flag the duplicates of a (finite) stream.
+
+/// Elements drawn uniformly from `[0, universe)`
+///
+/// # Example
+/// ```rust
+/// use qht::streams::Uniform;
+/// let stream: Vec<u64> = Uniform::new(100, 1).take(1000).collect();
+/// assert!( stream.iter().all(|&e| e < 100) );
+/// assert_eq!( stream, Uniform::new(100, 1).take(1000).collect::<Vec<_>>() );
+/// ```
+pub struct Uniform {
+    /// Random number generator
+    rng: StdRng,
+
+    /// Number of distinct possible elements
+    universe: u64,
+}
+
+impl Uniform {
+    /// Returns a stream over `universe` elements, or panics if `universe` is zero
+    pub fn new(universe: u64, seed: u64) -> Self {
+        if universe == 0 {
+            panic!("[Uniform stream] Incorrect parameters, universe cannot be zero.");
+        }
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            universe,
+        }
+    }
+}
+
+impl Iterator for Uniform {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        Some(self.rng.gen_range(0, self.universe))
+    }
+}
+
+/// Elements drawn from `[0, universe)` following Zipf's law: element `k` has a probability
+/// proportional to 1 / (k + 1)^`exponent`
+///
+/// The cumulative distribution is precomputed, which takes `universe` floats.
+///
+/// # Example
+/// ```rust
+/// use qht::streams::Zipf;
+/// let stream: Vec<u64> = Zipf::new(1000, 1.1, 1).take(10_000).collect();
+/// let first = stream.iter().filter(|&&e| e == 0).count();
+/// let last = stream.iter().filter(|&&e| e == 999).count();
+/// assert!( first > 100 * last );
+/// ```
+pub struct Zipf {
+    /// Random number generator
+    rng: StdRng,
+
+    /// Cumulative (unnormalized) probabilities of the elements
+    cumulative: Vec<f64>,
+}
+
+impl Zipf {
+    /// Returns a stream over `universe` elements, or panics if `universe` is zero or `exponent`
+    /// is not positive
+    pub fn new(universe: usize, exponent: f64, seed: u64) -> Self {
+        if universe == 0 {
+            panic!("[Zipf stream] Incorrect parameters, universe cannot be zero.");
+        }
+        if exponent.is_nan() || exponent <= 0.0 {
+            panic!("[Zipf stream] Incorrect parameters, exponent should be positive.");
+        }
+
+        let mut cumulative = Vec::with_capacity(universe);
+        let mut total = 0.0;
+        for rank in 1..=universe {
+            total += (rank as f64).powf(-exponent);
+            cumulative.push(total);
+        }
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            cumulative,
+        }
+    }
+}
+
+impl Iterator for Zipf {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        // Inverts the cumulative distribution
+        let last = self.cumulative.len() - 1;
+        let x = self.rng.gen::<f64>() * self.cumulative[last];
+        let rank = match self
+            .cumulative
+            .binary_search_by(|w| w.partial_cmp(&x).unwrap())
+        {
+            Ok(rank) | Err(rank) => rank,
+        };
+        Some(rank.min(last) as u64)
+    }
+}
+
+/// Bursts of `burst_length` elements, each drawn uniformly from `burst_width` elements picked
+/// uniformly from `[0, universe)` at the start of the burst
+///
+/// Duplicates are thus close to each other in the stream.
+///
+/// # Example
+/// ```rust
+/// use qht::streams::Bursty;
+/// let stream: Vec<u64> = Bursty::new(1 << 40, 100, 10, 1).take(100).collect();
+/// let mut burst = stream.clone();
+/// burst.sort();
+/// burst.dedup();
+/// assert!( burst.len() <= 10 );
+/// ```
+pub struct Bursty {
+    /// Random number generator
+    rng: StdRng,
+
+    /// Number of distinct possible elements
+    universe: u64,
+
+    /// Number of elements of each burst
+    burst_length: usize,
+
+    /// Elements of the current burst
+    hot: Vec<u64>,
+
+    /// Number of elements left in the current burst
+    remaining: usize,
+}
+
+impl Bursty {
+    /// Returns a bursty stream, or panics if a parameter is zero
+    pub fn new(universe: u64, burst_length: usize, burst_width: usize, seed: u64) -> Self {
+        if universe == 0 || burst_length == 0 || burst_width == 0 {
+            panic!("[Bursty stream] Incorrect parameters, universe, burst_length and burst_width cannot be zero.");
+        }
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            universe,
+            burst_length,
+            hot: vec![0; burst_width],
+            remaining: 0,
+        }
+    }
+}
+
+impl Iterator for Bursty {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.remaining == 0 {
+            for e in self.hot.iter_mut() {
+                *e = self.rng.gen_range(0, self.universe);
+            }
+            self.remaining = self.burst_length;
+        }
+        self.remaining -= 1;
+        let idx = self.rng.gen_range(0, self.hot.len());
+        Some(self.hot[idx])
+    }
+}
+
+/// Fresh elements, each repeated exactly `delay` positions later with probability `probability`
+///
+/// Repetitions are themselves repeated with the same probability. This controls the distance
+/// between duplicates, which filters with bounded memory eventually forget.
+///
+/// # Example
+/// ```rust
+/// use qht::streams::DelayedDuplicates;
+/// let stream: Vec<u64> = DelayedDuplicates::new(5, 0.5, 1).take(1000).collect();
+/// for i in 0..1000 {
+///     // Elements first seen earlier are exactly `delay` positions apart
+///     if let Some(j) = stream[..i].iter().rposition(|&e| e == stream[i]) {
+///         assert_eq!( i - j, 5 );
+///     }
+/// }
+/// ```
+pub struct DelayedDuplicates {
+    /// Random number generator
+    rng: StdRng,
+
+    /// Probability of repeating the element `delay` positions earlier
+    probability: f64,
+
+    /// Last `delay` elements, oldest first
+    window: VecDeque<u64>,
+
+    /// Number of positions between an element and its repetition
+    delay: usize,
+
+    /// Next fresh element
+    fresh: u64,
+}
+
+impl DelayedDuplicates {
+    /// Returns a stream repeating elements after `delay` positions, or panics if `delay` is zero
+    /// or `probability` is not in [0, 1]
+    pub fn new(delay: usize, probability: f64, seed: u64) -> Self {
+        if delay == 0 {
+            panic!("[DelayedDuplicates stream] Incorrect parameters, delay cannot be zero.");
+        }
+        if !(0.0..=1.0).contains(&probability) {
+            panic!(
+                "[DelayedDuplicates stream] Incorrect parameters, probability should be in [0, 1]."
+            );
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let fresh = rng.gen();
+        Self {
+            rng,
+            probability,
+            window: VecDeque::with_capacity(delay),
+            delay,
+            fresh,
+        }
+    }
+}
+
+impl Iterator for DelayedDuplicates {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let repeated = if self.window.len() == self.delay {
+            self.window.pop_front()
+        } else {
+            None
+        };
+        let e = match repeated {
+            Some(e) if self.rng.gen_bool(self.probability) => e,
+            _ => {
+                self.fresh = self.fresh.wrapping_add(1);
+                self.fresh
+            }
+        };
+        self.window.push_back(e);
+        Some(e)
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Ground truth
+
+/// Flags each element of a stream with whether it appeared before, see `label`
+pub struct Labelled<I: Iterator> {
+    /// Underlying stream
+    stream: I,
+
+    /// Elements seen so far
+    seen: HashSet<I::Item>,
+}
+
+/// Flags each element of `stream` with whether it is a duplicate, using an exact set
+///
+/// # Example
+/// ```rust
+/// use qht::streams::label;
+/// let labelled: Vec<(u64, bool)> = label(vec![1, 2, 1, 3, 2]).collect();
+/// assert_eq!( labelled, vec![(1, false), (2, false), (1, true), (3, false), (2, true)] );
+/// ```
+pub fn label<I>(stream: I) -> Labelled<I::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: Clone + Eq + Hash,
+{
+    Labelled {
+        stream: stream.into_iter(),
+        seen: HashSet::new(),
+    }
+}
+
+impl<I> Iterator for Labelled<I>
+where
+    I: Iterator,
+    I::Item: Clone + Eq + Hash,
+{
+    type Item = (I::Item, bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let e = self.stream.next()?;
+        let duplicate = !self.seen.insert(e.clone());
+        Some((e, duplicate))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
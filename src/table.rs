@@ -0,0 +1,805 @@
+use crate::basicqht::*;
+use crate::filter::{DynFilter, Filter};
+use crate::hash::{key_hash, DefaultBuildHasher};
+use crate::policy::InsertPolicy;
+use crate::simd;
+use crate::storage::{CellStorage, DefaultStorage, OwnedCellStorage};
+
+use core::hash::{BuildHasher, Hash};
+
+// --------------------------------------------------------------------------------
+
+/// Quotient Hash Table, generic over its insertion policy
+///
+/// `QuotientHashTable`, `QQuotientHashTable` and `QQuotientHashTableD` are `Qht`s using the
+/// `RandomEviction`, `QueuedRandomEviction` and `FifoShift` policies respectively. Other policies
+/// can be plugged in by implementing `InsertPolicy`.
+///
+/// Each element is hashed once: the highest bits of its hash give its cell, through a multiply-shift
+/// range reduction, and the lowest bits its fingerprint.
+///
+/// A dense bitset is used as the underlying data structure by default (see `CellStorage` for alternatives).
+pub struct Qht<P, S = DefaultStorage, H = DefaultBuildHasher> {
+    /// Number of cells (automatically computed)
+    pub(crate) n_cells: usize,
+
+    /// Number of buckets
+    pub(crate) n_buckets: usize,
+
+    /// Size of the fingerprint (in bits)
+    pub(crate) fingerprint_size: usize,
+
+    /// Size of the fingerprint (positional, automatically computed)
+    pub(crate) pow_fingerprint_size: u64,
+
+    /// Size of the payload following each fingerprint (in bits, 0 for filters)
+    pub(crate) payload_size: usize,
+
+    /// Underlying data structure
+    pub(crate) qht: S,
+
+    /// Hash function
+    pub(crate) hasher: H,
+
+    /// Insertion policy
+    pub(crate) policy: P,
+
+    /// Number of candidate cells per element
+    pub(crate) choices: usize,
+
+    /// Shift extracting addresses from hashes, when the number of cells is a power of two
+    pub(crate) cell_shift: Option<u32>,
+}
+
+#[cfg(feature = "std")]
+impl<P: InsertPolicy + Default> Qht<P> {
+    /// Returns a newly created `Qht` or panics
+    ///
+    /// This function takes as arguments:
+    /// * `memory_size`: allocated memory for the filter, in bits
+    /// * `n_buckets`: number of buckets
+    /// * `fingerprint_size`: size of each fingerprint, in bits. Cannot exceed `FINGERPRINT_SIZE_LIMIT`.
+    ///
+    /// Parameters should be chosen in a consistent way, namely so that `memory_size` >= `n_buckets` * `fingerprint_size`
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::{FifoShift, Qht};
+    /// let f = Qht::<FifoShift>::new(1024, 1, 3);
+    /// ```
+    pub fn new(memory_size: usize, n_buckets: usize, fingerprint_size: usize) -> Self {
+        Self::new_in(memory_size, n_buckets, fingerprint_size)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P: InsertPolicy + Default, S: OwnedCellStorage> Qht<P, S> {
+    /// Returns a newly created `Qht` using `S` as its underlying data structure, or panics
+    ///
+    /// Arguments are the same as for `Qht::new`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::QuotientHashTable;
+    /// let f = QuotientHashTable::<Vec<u64>>::new_in(1024, 1, 3);
+    /// ```
+    pub fn new_in(memory_size: usize, n_buckets: usize, fingerprint_size: usize) -> Self {
+        Self::with_hasher(
+            memory_size,
+            n_buckets,
+            fingerprint_size,
+            DefaultBuildHasher::default(),
+            P::default(),
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P: InsertPolicy + Default, S: CellStorage> Qht<P, S> {
+    /// Returns a `Qht` operating over the provided storage, or panics
+    ///
+    /// The number of cells is deduced from the storage's length. The storage is used as is,
+    /// hence it should be cleared beforehand unless it holds a previous table with the same parameters.
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::QuotientHashTable;
+    /// let mut memory = [0u64; 16];
+    /// let f = QuotientHashTable::with_storage(&mut memory[..], 1, 3);
+    /// ```
+    pub fn with_storage(qht: S, n_buckets: usize, fingerprint_size: usize) -> Self {
+        Self::from_parts(
+            qht,
+            n_buckets,
+            fingerprint_size,
+            DefaultBuildHasher::default(),
+            P::default(),
+        )
+    }
+}
+
+impl<P: InsertPolicy, S: OwnedCellStorage, H: BuildHasher> Qht<P, S, H> {
+    /// Returns a newly created `Qht` using the provided hasher and policy, or panics
+    ///
+    /// Other arguments are the same as for `Qht::new`. This constructor is available without the `std` feature.
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::hash::FnvBuildHasher;
+    /// use qht::{QuotientHashTable, RandomEviction};
+    /// use rand::{rngs::StdRng, SeedableRng};
+    /// let policy = RandomEviction::with_rng(StdRng::seed_from_u64(0));
+    /// let f = QuotientHashTable::<Vec<u64>, _>::with_hasher(1024, 1, 3, FnvBuildHasher::default(), policy);
+    /// ```
+    pub fn with_hasher(
+        memory_size: usize,
+        n_buckets: usize,
+        fingerprint_size: usize,
+        hasher: H,
+        policy: P,
+    ) -> Self {
+        let n_cells = cells_for_memory(memory_size, n_buckets, fingerprint_size);
+        Self::with_payload(n_cells, n_buckets, fingerprint_size, 0, hasher, policy)
+    }
+
+    /// Returns a newly created `Qht` of `n_cells` cells, whose buckets hold `payload_size` bits after the fingerprint
+    pub(crate) fn with_payload(
+        n_cells: usize,
+        n_buckets: usize,
+        fingerprint_size: usize,
+        payload_size: usize,
+        hasher: H,
+        policy: P,
+    ) -> Self {
+        // Initialise the storage with the appropriate length
+        let width = fingerprint_size + payload_size;
+        let qht = S::with_buckets(n_cells * n_buckets, width);
+
+        // The storage may be slightly larger than requested, but addressing only depends on `memory_size`
+        let mut filter = Self::from_payload_parts(
+            qht,
+            n_buckets,
+            fingerprint_size,
+            payload_size,
+            hasher,
+            policy,
+        );
+        filter.set_n_cells(n_cells);
+        filter
+    }
+}
+
+impl<P: InsertPolicy, S: CellStorage, H: BuildHasher> Qht<P, S, H> {
+    /// Returns a `Qht` operating over the provided storage, with the provided hasher and policy, or panics
+    ///
+    /// See `Qht::with_storage`. This constructor is available without the `std` feature.
+    pub fn from_parts(
+        qht: S,
+        n_buckets: usize,
+        fingerprint_size: usize,
+        hasher: H,
+        policy: P,
+    ) -> Self {
+        Self::from_payload_parts(qht, n_buckets, fingerprint_size, 0, hasher, policy)
+    }
+
+    fn from_payload_parts(
+        qht: S,
+        n_buckets: usize,
+        fingerprint_size: usize,
+        payload_size: usize,
+        hasher: H,
+        mut policy: P,
+    ) -> Self {
+        check_parameters(n_buckets, fingerprint_size);
+
+        let pow_fingerprint_size = 2u64.pow(fingerprint_size as u32);
+        let n_cells = qht.capacity(fingerprint_size + payload_size) / n_buckets;
+
+        // There should be at least one cell
+        if n_cells == 0 {
+            panic!(
+                "[QHT Filter] Incorrect parameters, storage should hold at least n_buckets buckets"
+            );
+        }
+        policy.init(n_cells, n_buckets);
+
+        Self {
+            n_cells,
+            n_buckets,
+            fingerprint_size,
+            pow_fingerprint_size,
+            payload_size,
+            qht,
+            hasher,
+            policy,
+            choices: 1,
+            cell_shift: cell_shift(n_cells),
+        }
+    }
+
+    /// Returns the insertion policy
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// Restricts addressing to the first `n_cells` cells of the storage
+    pub(crate) fn set_n_cells(&mut self, n_cells: usize) {
+        if n_cells != self.n_cells && !self.qht.resizable() {
+            panic!(
+                "[QHT Filter] Incorrect parameters, the number of cells of this storage is fixed."
+            );
+        }
+        self.n_cells = n_cells;
+        self.cell_shift = cell_shift(n_cells);
+        self.policy.init(n_cells, self.n_buckets);
+    }
+
+    /// Gives each element `choices` candidate cells instead of one, or panics
+    ///
+    /// `choices` cannot be zero, nor exceed 255.
+    ///
+    /// Lookups check every candidate cell; new fingerprints go to the least loaded one, which evens
+    /// out the load of cells at the cost of more fingerprint comparisons (hence false positives).
+    /// File-backed tables record it in their header.
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::{Filter, QuotientHashTable};
+    /// let mut f = QuotientHashTable::new(1024, 4, 3).with_choices(2);
+    /// assert!( !f.insert(1234u64) );
+    /// assert!( f.lookup(1234u64) );
+    /// ```
+    pub fn with_choices(mut self, choices: usize) -> Self {
+        if choices == 0 {
+            panic!("[QHT Filter] Incorrect parameters, choices cannot be zero.");
+        } else if choices > 255 {
+            panic!("[QHT Filter] Incorrect parameters, choices cannot exceed 255.");
+        }
+        self.choices = choices;
+        self.qht.set_choices(choices);
+        self
+    }
+
+    /// Rounds the number of cells down to a power of two, so that addresses are computed with a shift
+    ///
+    /// The remaining memory is left unused. The number of cells of file-backed tables is fixed on
+    /// creation, so that this panics unless it is already a power of two: `memory_size` should
+    /// rather be chosen accordingly (see `cells_for_memory`).
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::{Filter, QuotientHashTable};
+    /// let mut f = QuotientHashTable::new(1000, 1, 3).with_power_of_two_cells();
+    /// assert_eq!( f.header().n_cells, 256 );
+    /// assert!( !f.insert(1234u64) );
+    /// assert!( f.lookup(1234u64) );
+    /// ```
+    pub fn with_power_of_two_cells(mut self) -> Self {
+        let n_cells = self.n_cells.next_power_of_two();
+        if n_cells > self.n_cells {
+            self.set_n_cells(n_cells / 2);
+        }
+        self
+    }
+
+    /// Returns the number of candidate cells per element
+    pub fn choices(&self) -> usize {
+        self.choices
+    }
+
+    /// Returns the address of the `choice`-th candidate cell of an element of hash `hash`
+    fn address(&self, hash: u64, choice: usize) -> usize {
+        let hash = candidate_hash(hash, choice);
+        match self.cell_shift {
+            Some(shift) => hash.checked_shr(shift).unwrap_or(0) as usize,
+            None => address_of(hash, self.n_cells),
+        }
+    }
+
+    /// Returns the hash of an element, from which its address and fingerprint are derived
+    pub(crate) fn hash(&self, e: impl Hash) -> u64 {
+        hash_of(&self.hasher, e)
+    }
+
+    /// Returns a cell, along with the policy managing it
+    fn cell(&mut self, address: usize) -> (Cell<'_, S>, &mut P) {
+        let cell = Cell::new(
+            &mut self.qht,
+            address,
+            self.n_buckets,
+            self.fingerprint_size + self.payload_size,
+            self.pow_fingerprint_size - 1,
+        );
+        (cell, &mut self.policy)
+    }
+
+    /// Returns the cell the element of hash `hash` goes to, along with the bucket already holding its fingerprint
+    ///
+    /// With several choices, this is the candidate cell holding the fingerprint, or else the least
+    /// loaded one.
+    fn target(&mut self, hash: u64, fingerprint: Fingerprint) -> (usize, Option<usize>) {
+        let mut target = (self.address(hash, 0), None);
+        let mut least_load = usize::max_value();
+        let choices = self.choices;
+        for choice in 0..choices {
+            let address = self.address(hash, choice);
+            let (cell, _) = self.cell(address);
+            if let Some(position) = cell.find(fingerprint) {
+                return (address, Some(position));
+            }
+            if choices > 1 {
+                let load = cell.load();
+                if load < least_load {
+                    target = (address, None);
+                    least_load = load;
+                }
+            }
+        }
+        target
+    }
+
+    /// Returns the payload stored with the fingerprint of the element of hash `hash`, if any
+    pub(crate) fn payload(&self, hash: u64) -> Option<u64> {
+        let fingerprint = fingerprint_of(hash, self.pow_fingerprint_size);
+        let width = self.fingerprint_size + self.payload_size;
+
+        (0..self.choices).find_map(|choice| {
+            let first = self.address(hash, choice) * self.n_buckets;
+            find_in_cell(
+                &self.qht,
+                first,
+                self.n_buckets,
+                width,
+                self.pow_fingerprint_size - 1,
+                fingerprint,
+            )
+            .map(|idx| self.qht.get_bucket(first + idx, width) >> self.fingerprint_size)
+        })
+    }
+
+    /// Inserts the element of hash `hash` as decided by the policy, storing `payload(previous)`
+    /// with its fingerprint
+    ///
+    /// Returns the payload previously stored with the element's fingerprint, if any
+    pub(crate) fn insert_payload(
+        &mut self,
+        hash: u64,
+        payload: impl FnOnce(Option<u64>) -> u64,
+    ) -> Option<u64> {
+        let fingerprint = fingerprint_of(hash, self.pow_fingerprint_size);
+        let (fingerprint_size, mask) = (self.fingerprint_size, self.pow_fingerprint_size - 1);
+
+        let (address, position) = self.target(hash, fingerprint);
+        let (mut cell, policy) = self.cell(address);
+        let previous = position.map(|idx| cell.get(idx) >> fingerprint_size);
+
+        // Policies may keep several copies of a fingerprint: all of them are updated
+        if policy.insert(&mut cell, fingerprint, position).is_some() {
+            let bucket = fingerprint | (payload(previous) << fingerprint_size);
+            for idx in 0..cell.n_buckets() {
+                if cell.get(idx) & mask == fingerprint {
+                    cell.set(idx, bucket);
+                }
+            }
+        }
+        previous
+    }
+
+    /// Performs a lookup for the element of hash `hash`
+    ///
+    /// This skips hashing for callers that already hold a 64-bit hash of their elements: its highest
+    /// bits give the cell and its lowest bits the fingerprint, hence it should be uniformly distributed.
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::QuotientHashTable;
+    /// let mut f = QuotientHashTable::new(1024, 4, 3);
+    /// let hash = 0x9e37_79b9_7f4a_7c15;
+    /// assert!( !f.insert_hash(hash) );
+    /// assert!( f.lookup_hash(hash) );
+    /// ```
+    pub fn lookup_hash(&self, hash: u64) -> bool {
+        let fingerprint = fingerprint_of(hash, self.pow_fingerprint_size);
+        (0..self.choices).any(|choice| self.in_cell(self.address(hash, choice), fingerprint))
+    }
+
+    /// Performs a lookup for the element of hash `hash` and inserts it, as decided by the policy
+    ///
+    /// See `lookup_hash`.
+    pub fn insert_hash(&mut self, hash: u64) -> bool {
+        let fingerprint = fingerprint_of(hash, self.pow_fingerprint_size);
+
+        let (address, position) = self.target(hash, fingerprint);
+        let (mut cell, policy) = self.cell(address);
+        policy.insert(&mut cell, fingerprint, position);
+
+        position.is_some()
+    }
+
+    /// Performs a lookup for a byte-string key
+    ///
+    /// Keys are hashed with `hash::key_hash` rather than the table's hasher, so that filters can
+    /// be populated or queried by implementations in other languages.
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::QuotientHashTable;
+    /// let mut f = QuotientHashTable::new(1024, 4, 3);
+    /// assert!( !f.insert_key("alice") );
+    /// assert!( f.lookup_key(b"alice") );
+    /// ```
+    pub fn lookup_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.lookup_hash(key_hash(key))
+    }
+
+    /// Performs a lookup for a byte-string key and inserts it, as decided by the policy
+    ///
+    /// See `lookup_key`.
+    pub fn insert_key(&mut self, key: impl AsRef<[u8]>) -> bool {
+        self.insert_hash(key_hash(key))
+    }
+
+    /// Performs a lookup for the element of 128-bit hash `hash`
+    ///
+    /// Both halves of `hash` are folded together, see `lookup_hash`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::QQuotientHashTableD;
+    /// let mut f = QQuotientHashTableD::new(1024, 4, 3);
+    /// let hash = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210;
+    /// assert!( !f.insert_hash128(hash) );
+    /// assert!( f.lookup_hash128(hash) );
+    /// ```
+    pub fn lookup_hash128(&self, hash: u128) -> bool {
+        self.lookup_hash(fold_hash(hash))
+    }
+
+    /// Performs a lookup for the element of 128-bit hash `hash` and inserts it, as decided by the policy
+    ///
+    /// See `lookup_hash128`.
+    pub fn insert_hash128(&mut self, hash: u128) -> bool {
+        self.insert_hash(fold_hash(hash))
+    }
+}
+
+impl<P: InsertPolicy, S: CellStorage, H: BuildHasher> BasicQHT for Qht<P, S, H> {
+    /// Retrieves a fingerprint from a given bucket (provided as an `address` and `bucket_number`)
+    fn get_fingerprint_from_bucket(&self, address: usize, bucket_number: usize) -> Fingerprint {
+        self.qht.get_bucket(
+            address * self.n_buckets + bucket_number,
+            self.fingerprint_size + self.payload_size,
+        ) & (self.pow_fingerprint_size - 1)
+    }
+
+    /// Inserts a fingerprint in a given buffer (provided as an `address` and `bucket_number`)
+    fn insert_fingerprint_in_bucket(
+        &mut self,
+        address: usize,
+        bucket_number: usize,
+        fingerprint: Fingerprint,
+    ) {
+        self.qht.set_bucket(
+            address * self.n_buckets + bucket_number,
+            self.fingerprint_size + self.payload_size,
+            fingerprint,
+        );
+    }
+
+    /// Checks whether a fingerprint belongs to a given cell
+    fn in_cell(&self, address: usize, fingerprint: Fingerprint) -> bool {
+        find_in_cell(
+            &self.qht,
+            address * self.n_buckets,
+            self.n_buckets,
+            self.fingerprint_size + self.payload_size,
+            self.pow_fingerprint_size - 1,
+            fingerprint,
+        )
+        .is_some()
+    }
+
+    /// Obtains an element's fingerprint
+    fn get_fingerprint(&self, e: impl Hash) -> Fingerprint {
+        fingerprint_of(self.hash(e), self.pow_fingerprint_size)
+    }
+}
+
+impl<P: InsertPolicy, S: CellStorage, H: BuildHasher> Filter for Qht<P, S, H> {
+    /// Performs a lookup for the provided element
+    fn lookup(&self, e: impl Hash) -> bool {
+        self.lookup_hash(self.hash(e))
+    }
+
+    /// Performs a lookup for an element and inserts it, as decided by the policy
+    ///
+    /// With several choices, the element goes to the candidate cell holding its fingerprint,
+    /// or else to the least loaded one.
+    fn insert(&mut self, e: impl Hash) -> bool {
+        self.insert_hash(self.hash(e))
+    }
+}
+
+impl<P: InsertPolicy, S: CellStorage, H: BuildHasher> DynFilter for Qht<P, S, H> {
+    fn lookup_hash(&self, hash: u64) -> bool {
+        Qht::lookup_hash(self, hash)
+    }
+
+    fn insert_hash(&mut self, hash: u64) -> bool {
+        Qht::insert_hash(self, hash)
+    }
+}
+
+/// Returns the shift extracting addresses from hashes, if `n_cells` is a power of two
+fn cell_shift(n_cells: usize) -> Option<u32> {
+    if n_cells.is_power_of_two() {
+        Some(64 - n_cells.trailing_zeros())
+    } else {
+        None
+    }
+}
+
+// --------------------------------------------------------------------------------
+// Cells
+
+/// Buckets of a cell, as handed over to an `InsertPolicy`
+pub struct Cell<'a, S> {
+    /// Underlying data structure
+    storage: &'a mut S,
+
+    /// Address of the cell
+    address: usize,
+
+    /// Number of buckets
+    n_buckets: usize,
+
+    /// Size of a bucket (in bits)
+    width: usize,
+
+    /// Bits of a bucket holding the fingerprint
+    fingerprint_mask: Fingerprint,
+}
+
+impl<'a, S: CellStorage> Cell<'a, S> {
+    /// Returns the cell at `address` of `storage`, whose buckets of `width` bits hold fingerprints
+    /// in the bits of `fingerprint_mask`
+    pub(crate) fn new(
+        storage: &'a mut S,
+        address: usize,
+        n_buckets: usize,
+        width: usize,
+        fingerprint_mask: Fingerprint,
+    ) -> Self {
+        Self {
+            storage,
+            address,
+            n_buckets,
+            width,
+            fingerprint_mask,
+        }
+    }
+
+    /// Returns the address of the cell
+    pub fn address(&self) -> usize {
+        self.address
+    }
+
+    /// Returns the number of buckets in the cell
+    pub fn n_buckets(&self) -> usize {
+        self.n_buckets
+    }
+
+    /// Obtains the content of a given bucket (0 if empty)
+    ///
+    /// This is the fingerprint, followed by its payload in counting tables and maps
+    pub fn get(&self, bucket_number: usize) -> Fingerprint {
+        self.storage
+            .get_bucket(self.first() + bucket_number, self.width)
+    }
+
+    /// Overwrites a given bucket with a fingerprint
+    pub fn set(&mut self, bucket_number: usize, fingerprint: Fingerprint) {
+        self.storage
+            .set_bucket(self.first() + bucket_number, self.width, fingerprint)
+    }
+
+    /// Returns the first bucket holding `fingerprint`, if any
+    pub fn find(&self, fingerprint: Fingerprint) -> Option<usize> {
+        find_in_cell(
+            &*self.storage,
+            self.first(),
+            self.n_buckets,
+            self.width,
+            self.fingerprint_mask,
+            fingerprint,
+        )
+    }
+
+    /// Returns the number of non-empty buckets
+    pub fn load(&self) -> usize {
+        (0..self.n_buckets)
+            .filter(|&idx| self.get(idx) & self.fingerprint_mask != 0)
+            .count()
+    }
+
+    /// Inserts the fingerprint in the first empty bucket
+    ///
+    /// Returns that bucket, or `None` if no empty bucket exists and the insertion failed
+    pub fn insert_empty(&mut self, fingerprint: Fingerprint) -> Option<usize> {
+        let bucket = self.find(0)?;
+        self.set(bucket, fingerprint);
+        Some(bucket)
+    }
+
+    /// Moves the content of bucket `from` to bucket `to`, shifting the buckets in between by one position
+    pub fn rotate(&mut self, from: usize, to: usize) {
+        let moved = self.get(from);
+        if from < to {
+            for idx in from..to {
+                let next = self.get(idx + 1);
+                self.set(idx, next);
+            }
+        } else {
+            for idx in (to + 1..=from).rev() {
+                let prev = self.get(idx - 1);
+                self.set(idx, prev);
+            }
+        }
+        self.set(to, moved);
+    }
+
+    fn first(&self) -> usize {
+        self.address * self.n_buckets
+    }
+}
+
+/// Returns the first of the `n_buckets` buckets starting at `first` holding `fingerprint`, if any
+///
+/// Only the bits of `mask` are compared. Wide cells are loaded by chunks and matched with `simd::position`
+pub(crate) fn find_in_cell<S: CellStorage>(
+    storage: &S,
+    first: usize,
+    n_buckets: usize,
+    width: usize,
+    mask: Fingerprint,
+    fingerprint: Fingerprint,
+) -> Option<usize> {
+    if n_buckets < simd::SIMD_MIN_BUCKETS {
+        return (0..n_buckets)
+            .find(|&idx| storage.get_bucket(first + idx, width) & mask == fingerprint);
+    }
+
+    // Fingerprints are narrower than a byte, so wider ones never match
+    if fingerprint & !mask != 0 {
+        return None;
+    }
+
+    let mut buckets = [0; simd::SIMD_CHUNK];
+    let mut cell = [0u8; simd::SIMD_CHUNK];
+
+    let mut start = 0;
+    while start < n_buckets {
+        let len = core::cmp::min(simd::SIMD_CHUNK, n_buckets - start);
+        storage.load_cell(first + start, width, &mut buckets[..len]);
+        for (byte, &bucket) in cell.iter_mut().zip(&buckets[..len]) {
+            *byte = (bucket & mask) as u8;
+        }
+
+        if let Some(idx) = simd::position(&cell[..len], fingerprint as u8) {
+            return Some(start + idx);
+        }
+        start += len;
+    }
+    None
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::basicqht::BasicQHT;
+    use crate::testing::error_rates;
+    use crate::{
+        CountingQuotientHashTable, Filter, QQuotientHashTable, QQuotientHashTableD, QhtMap,
+        QhtView, QuotientHashTable,
+    };
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Checks `in_cell` against a bucket-by-bucket scan on wide cells
+    ///
+    /// Fingerprints go past the bucket width, whose truncation must not match empty buckets.
+    fn check_in_cell_equivalence<F: BasicQHT>(f: &mut F, n_cells: usize, n_buckets: usize) {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..2000 {
+            f.insert(rng.gen_range(0, 5000u64));
+        }
+        for address in 0..n_cells {
+            for fingerprint in 0..512 {
+                let expected = (0..n_buckets)
+                    .any(|idx| f.get_fingerprint_from_bucket(address, idx) == fingerprint);
+                assert_eq!(f.in_cell(address, fingerprint), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn in_cell_simd_matches_scalar() {
+        for &(n_buckets, fingerprint_size) in &[(16, 3), (37, 5), (100, 8), (130, 7)] {
+            let n_cells = 4;
+            let memory = n_cells * n_buckets * fingerprint_size;
+            check_in_cell_equivalence(
+                &mut QuotientHashTable::new(memory, n_buckets, fingerprint_size),
+                n_cells,
+                n_buckets,
+            );
+            check_in_cell_equivalence(
+                &mut QQuotientHashTable::new(memory, n_buckets, fingerprint_size),
+                n_cells,
+                n_buckets,
+            );
+            check_in_cell_equivalence(
+                &mut QQuotientHashTableD::new(memory, n_buckets, fingerprint_size),
+                n_cells,
+                n_buckets,
+            );
+        }
+
+        // Sparse cells keep empty buckets, which a truncated fingerprint 256 would match
+        let mut f = QuotientHashTable::new(4 * 64 * 8, 64, 8);
+        f.insert(1u64);
+        for address in 0..4 {
+            assert!(!f.in_cell(address, 256));
+            assert!(!f.in_cell(address, 512));
+        }
+    }
+
+    #[test]
+    fn two_choices_reduce_false_negatives() {
+        let mut rng = StdRng::seed_from_u64(13);
+        let stream: Vec<u64> = (0..100_000).map(|_| rng.gen_range(0, 4_000)).collect();
+        let (memory, n_buckets, fingerprint_size) = (40_000, 4, 8);
+
+        let mut rates = Vec::new();
+        for choices in 1..=3 {
+            let f =
+                QuotientHashTable::new(memory, n_buckets, fingerprint_size).with_choices(choices);
+            rates.push(error_rates(f, &stream));
+        }
+
+        // Balanced cells evict less, while lookups compare more fingerprints
+        assert!(rates[1].1 < rates[0].1);
+        assert!(rates[1].0 > rates[0].0);
+
+        // Choices are serialized
+        let f = QuotientHashTable::new(memory, n_buckets, fingerprint_size).with_choices(2);
+        let g = QuotientHashTable::from_bytes(&f.to_bytes()).unwrap();
+        assert_eq!(g.choices(), 2);
+    }
+
+    #[test]
+    fn precomputed_hashes() {
+        let mut rng = StdRng::seed_from_u64(17);
+        let hashes: Vec<u128> = (0..2000).map(|_| rng.gen()).collect();
+
+        let mut f = QuotientHashTable::new(100_000, 4, 8).with_choices(2);
+        let mut counting = CountingQuotientHashTable::new(400_000, 4, 8, 2);
+        let mut map = QhtMap::<u16>::new(4_000_000, 4, 8, 16);
+        for (idx, &hash) in hashes.iter().enumerate() {
+            f.insert_hash128(hash);
+            counting.insert_hash128(hash);
+            map.insert_hash128(hash, idx as u16);
+        }
+
+        let bytes = f.to_bytes();
+        let view = QhtView::new(&bytes).unwrap();
+        for &hash in &hashes {
+            assert!(f.lookup_hash128(hash));
+            assert!(view.lookup_hash128(hash));
+            assert!(counting.count_hash128(hash) >= 1);
+            assert!(map.get_hash128(hash).is_some());
+        }
+        assert_eq!(counting.count_key("alice"), 0);
+        assert_eq!(map.insert_key("alice", 7), None);
+        assert_eq!(map.get_key(b"alice"), Some(7));
+    }
+}
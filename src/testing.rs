@@ -0,0 +1,25 @@
+use crate::accuracy::AccuracyTracker;
+use crate::filter::Filter;
+
+// --------------------------------------------------------------------------------
+// Helpers shared by the tests of several modules
+
+/// Returns the false positive and false negative rates of `f` over `stream`
+pub fn error_rates<F: Filter>(f: F, stream: &[u64]) -> (f64, f64) {
+    let mut tracker = AccuracyTracker::new(f);
+    for &e in stream {
+        tracker.insert(e);
+    }
+    let total = tracker.total();
+    (total.false_positive_rate(), total.false_negative_rate())
+}
+
+/// Returns the false positive and false negative rates of `f` over the last tenth of `stream`
+pub fn last_error_rates<F: Filter>(f: F, stream: &[u64]) -> (f64, f64) {
+    let mut tracker = AccuracyTracker::new(f).with_period(stream.len() / 10);
+    for &e in stream {
+        tracker.insert(e);
+    }
+    let last = tracker.windows()[9];
+    (last.false_positive_rate(), last.false_negative_rate())
+}
@@ -0,0 +1,188 @@
+use crate::basicqht::*;
+use crate::format::{read_bits, Flavour, FormatError, HasherId, Header, HEADER_LEN};
+use crate::hash::{key_hash, DefaultBuildHasher};
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash};
+
+// --------------------------------------------------------------------------------
+
+/// Read-only view of a serialized filter
+///
+/// A `QhtView` borrows a buffer in the crate's serialized format (as produced by `to_bytes`,
+/// or a file-backed filter) and performs lookups in place, without copying or allocating.
+/// Views can be taken over filters of any flavour.
+pub struct QhtView<'a, H = DefaultBuildHasher> {
+    /// Parameters read from the header
+    header: Header,
+
+    /// Size of the fingerprint (positional, automatically computed)
+    pow_fingerprint_size: u64,
+
+    /// Packed buckets
+    payload: &'a [u8],
+
+    /// Hash function
+    hasher: H,
+}
+
+impl<'a> QhtView<'a> {
+    /// Returns a view of the serialized filter held in `bytes`
+    ///
+    /// Fails if `bytes` does not hold exactly a serialized filter.
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::{Element, Filter, QQuotientHashTable, QhtView};
+    /// let mut f = QQuotientHashTable::new(1024, 4, 3);
+    /// f.insert(Element { value: 1234 });
+    ///
+    /// let bytes = f.to_bytes();
+    /// let view = QhtView::new(&bytes).unwrap();
+    /// assert!( view.lookup(Element { value: 1234 }) );
+    /// assert!( QhtView::new(&bytes[1..]).is_err() );
+    /// ```
+    pub fn new(bytes: &'a [u8]) -> Result<Self, FormatError> {
+        Self::with_hasher(bytes, DefaultBuildHasher::default())
+    }
+}
+
+impl<'a, H: BuildHasher + 'static> QhtView<'a, H> {
+    /// Returns a view of the serialized filter held in `bytes`, populated using `hasher`
+    ///
+    /// Fails if `bytes` does not hold exactly a serialized filter, or if it was populated with another hasher.
+    pub fn with_hasher(bytes: &'a [u8], hasher: H) -> Result<Self, FormatError> {
+        let header = Header::decode_exact(bytes)?;
+        if header.hasher != HasherId::of::<H>() {
+            return Err(FormatError::BadHasher(header.hasher as u8));
+        }
+
+        Ok(Self {
+            header,
+            pow_fingerprint_size: 2u64.pow(header.fingerprint_size as u32),
+            payload: &bytes[HEADER_LEN..],
+            hasher,
+        })
+    }
+
+    /// Returns the parameters of the viewed filter
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Returns the flavour of the viewed filter
+    pub fn flavour(&self) -> Flavour {
+        self.header.flavour
+    }
+
+    /// Performs a lookup for the provided element
+    pub fn lookup(&self, e: impl Hash) -> bool {
+        self.lookup_hash(hash_of(&self.hasher, e))
+    }
+
+    /// Performs a lookup for the element of hash `hash` (see `Qht::lookup_hash`)
+    pub fn lookup_hash(&self, hash: u64) -> bool {
+        let fingerprint = fingerprint_of(hash, self.pow_fingerprint_size);
+        (0..self.header.choices).any(|choice| {
+            let address = address_of(candidate_hash(hash, choice), self.header.n_cells);
+            self.in_cell(address, fingerprint)
+        })
+    }
+
+    /// Performs a lookup for a byte-string key (see `Qht::lookup_key`)
+    pub fn lookup_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.lookup_hash(key_hash(key))
+    }
+
+    /// Performs a lookup for the element of 128-bit hash `hash` (see `Qht::lookup_hash128`)
+    pub fn lookup_hash128(&self, hash: u128) -> bool {
+        self.lookup_hash(fold_hash(hash))
+    }
+
+    /// Returns the number of non-empty buckets of a given cell
+    pub fn load(&self, address: usize) -> usize {
+        let width = self.header.fingerprint_size;
+        let first = address * self.header.n_buckets;
+        (first..first + self.header.n_buckets)
+            .filter(|&idx| read_bits(self.payload, idx * width, width) != 0)
+            .count()
+    }
+
+    /// Returns the number of cells having 0, 1, ..., `n_buckets` non-empty buckets
+    ///
+    /// # Example
+    /// ```rust
+    /// use qht::{Filter, QhtView, QuotientHashTable};
+    /// let mut f = QuotientHashTable::new(1024, 4, 8);
+    /// f.insert(1234u64);
+    ///
+    /// let bytes = f.to_bytes();
+    /// let view = QhtView::new(&bytes).unwrap();
+    /// assert_eq!( view.occupancy(), vec![31, 1, 0, 0, 0] );
+    /// ```
+    pub fn occupancy(&self) -> Vec<usize> {
+        let mut histogram = vec![0; self.header.n_buckets + 1];
+        for address in 0..self.header.n_cells {
+            histogram[self.load(address)] += 1;
+        }
+        histogram
+    }
+
+    /// Returns the estimated probability that a lookup for an element that was never inserted succeeds
+    ///
+    /// Fingerprints are assumed uniformly distributed: a non-empty bucket matches with probability
+    /// 1 / (2^`fingerprint_size` - 1).
+    pub fn estimated_fpr(&self) -> f64 {
+        let miss = 1.0 - 1.0 / (self.pow_fingerprint_size - 1) as f64;
+
+        // Probability that a random cell holds no matching fingerprint
+        let mut cell_miss = 0.0;
+        let mut miss_load = 1.0;
+        for &cells in &self.occupancy() {
+            cell_miss += cells as f64 * miss_load;
+            miss_load *= miss;
+        }
+        cell_miss /= self.header.n_cells as f64;
+
+        let mut all_miss = 1.0;
+        for _ in 0..self.header.choices {
+            all_miss *= cell_miss;
+        }
+        1.0 - all_miss
+    }
+
+    /// Checks whether a fingerprint belongs to a given cell
+    fn in_cell(&self, address: usize, fingerprint: Fingerprint) -> bool {
+        let width = self.header.fingerprint_size;
+        let first = address * self.header.n_buckets;
+        (first..first + self.header.n_buckets)
+            .any(|idx| read_bits(self.payload, idx * width, width) == fingerprint)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::QhtView;
+    use crate::{Filter, QuotientHashTable};
+
+    #[test]
+    fn estimated_fpr_matches_measurements() {
+        for &choices in &[1, 2] {
+            let mut f = QuotientHashTable::new(40_000, 4, 5).with_choices(choices);
+            for value in 0..1500u64 {
+                f.insert(value);
+            }
+            let bytes = f.to_bytes();
+            let view = QhtView::new(&bytes).unwrap();
+
+            let trials = 100_000;
+            let positives = (1_000_000..1_000_000 + trials as u64)
+                .filter(|&value| view.lookup(value))
+                .count();
+            let measured = positives as f64 / trials as f64;
+            let estimated = view.estimated_fpr();
+            assert!((measured - estimated).abs() < 0.1 * estimated);
+        }
+    }
+}
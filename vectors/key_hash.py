@@ -0,0 +1,81 @@
+#!/usr/bin/env python3
+"""Reference implementation of the qht key hashing scheme (see `qht::hash::key_hash`).
+
+Prints the conformance vectors checked by the crate's tests:
+
+    python3 vectors/key_hash.py > vectors/key_hash.csv
+"""
+
+MASK = 2**64 - 1
+FNV_OFFSET_BASIS = 0xCBF29CE484222325
+FNV_PRIME = 0x100000001B3
+GOLDEN = 0x9E3779B97F4A7C15
+
+
+def fnv1a(data):
+    h = FNV_OFFSET_BASIS
+    for byte in data:
+        h = ((h ^ byte) * FNV_PRIME) & MASK
+    return h
+
+
+def mix(z):
+    """SplitMix64 finalizer"""
+    z &= MASK
+    z = ((z ^ (z >> 30)) * 0xBF58476D1CE4E5B9) & MASK
+    z = ((z ^ (z >> 27)) * 0x94D049BB133111EB) & MASK
+    return z ^ (z >> 31)
+
+
+def key_hash(data):
+    return mix(fnv1a(data))
+
+
+def address(h, n_cells, choice=0):
+    if choice > 0:
+        h = mix(h + choice * GOLDEN)
+    return (h * n_cells) >> 64
+
+
+def fingerprint(h, fingerprint_size):
+    f = h % 2**fingerprint_size
+    k = 0
+    while f == 0:
+        k += 1
+        f = mix(h + k * GOLDEN) % 2**fingerprint_size
+    return f
+
+
+KEYS = [
+    b"",
+    b"a",
+    b"foobar",
+    b"hello world",
+    "héllo wörld".encode("utf-8"),
+    "重复".encode("utf-8"),
+    bytes(range(256)),
+    b"\x00",
+    b"\x00\x00\x00\x00",
+    b"192.168.0.1",
+    b"user:42;event:login",
+    b"x" * 1000,
+]
+
+PARAMETERS = [(1000, 3), (1024, 8), (1, 5), (123457, 1)]
+
+if __name__ == "__main__":
+    print("key,hash,n_cells,fingerprint_size,address,second_address,fingerprint")
+    for key in KEYS:
+        h = key_hash(key)
+        for n_cells, fingerprint_size in PARAMETERS:
+            print(
+                "{},{:016x},{},{},{},{},{}".format(
+                    key.hex(),
+                    h,
+                    n_cells,
+                    fingerprint_size,
+                    address(h, n_cells),
+                    address(h, n_cells, 1),
+                    fingerprint(h, fingerprint_size),
+                )
+            )